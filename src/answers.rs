@@ -0,0 +1,67 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use anyhow::*;
+use crate::run_on_day_input;
+
+// Verified answers by day and part, the `partN_final` tests of each day check against them
+pub const ANSWERS: [[Option<&str>; 2]; 25] = [
+    [None, None], // 01
+    [None, None], // 02
+    [None, None], // 03
+    [None, None], // 04
+    [None, None], // 05
+    [Some("5239"), Some("1753")], // 06
+    [None, None], // 07
+    [Some("423"), None], // 08
+    [Some("6241633730082"), Some("6265268809555")], // 09
+    [None, None], // 10
+    [Some("193607"), Some("229557103025807")], // 11
+    [None, Some("911750")], // 12
+    [Some("26005"), Some("105620095782547")], // 13
+    [Some("231852216"), None], // 14
+    [Some("1465523"), Some("1471049")], // 15
+    [Some("107468"), Some("533")], // 16
+    [Some("6,1,6,4,2,4,7,3,5"), None], // 17
+    [Some("340"), Some("34,32")], // 18
+    [Some("355"), None], // 19
+    [Some("1327"), Some("985737")], // 20
+    [Some("162740"), Some("203640915832208")], // 21
+    [None, Some("2272")], // 22
+    [Some("1194"), Some("bd,bu,dv,gl,qc,rn,so,tm,wf,yl,ys,ze,zr")], // 23
+    [Some("51657025112326"), None], // 24
+    [Some("3127"), None], // 25
+];
+
+pub fn answer(day: &str, part: usize) -> Option<&'static str> {
+    let day: usize = day.parse().ok()?;
+    *ANSWERS.get(day.checked_sub(1)?)?.get(part.checked_sub(1)?)?
+}
+
+// Runs a part on the day's input and compares the result with the verified answer
+pub fn check_answer<F, R>(day: &str, part: usize, operation: F) -> Result<()>
+where
+    F: Fn(BufReader<File>) -> Result<R>,
+    R: Display,
+{
+    let Some(expected) = answer(day, part) else {
+        bail!("Day {} part {} has no verified answer", day, part);
+    };
+    let result = run_on_day_input(day, operation)?.to_string();
+    ensure!(result == expected, "Day {} part {}: got {}, the verified answer is {}", day, part, result, expected);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer() {
+        assert_eq!(Some("5239"), answer("06", 1));
+        assert_eq!(None, answer("01", 2));
+        assert_eq!(None, answer("00", 1));
+        assert_eq!(None, answer("26", 1));
+        assert_eq!(None, answer("06", 3));
+    }
+}
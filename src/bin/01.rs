@@ -1,53 +1,60 @@
 use anyhow::*;
-use code_timing_macros::time_snippet;
-use const_format::concatcp;
+use std::collections::HashMap;
 use indoc::indoc;
 use itertools::Itertools;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-const DAY: &str = "01";
-const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+use std::io::BufRead;
+use advent_of_code2024_rust::{day, run_on_day_input};
+
+fn read_lists<R: BufRead>(reader: R) -> Result<(Vec<i64>, Vec<i64>)> {
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let numbers: Vec<i64> = line.split_whitespace()
+            .map(|x| x.parse::<i64>().map_err(|_| anyhow!("Line {}: invalid number \"{}\"", index + 1, x)))
+            .collect::<Result<Vec<i64>>>()?;
+        let [a, b] = numbers[..] else {
+            bail!("Line {}: expected two numbers, got {}", index + 1, numbers.len());
+        };
+        first.push(a);
+        second.push(b);
+    }
+    Ok((first, second))
+}
 
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let input_pairs: Vec<(i64, i64)> = reader.lines()
-        .flatten()
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let numbers: Vec<i64> = line.split("   ")
-                .map(|x| x.parse::<i64>().unwrap())
-                .collect();
-            (numbers[0], numbers[1])
-        })
-        .collect();
-
-    let first = input_pairs.iter().map(|(a, _b)| a).sorted();
-    let second = input_pairs.iter().map(|(_a, b)| b).sorted();
+    let (first, second) = read_lists(reader)?;
 
     let mut total_distance: i64 = 0;
-    for (f, s) in first.zip(second) {
+    for (f, s) in first.iter().sorted().zip(second.iter().sorted()) {
         total_distance += (f - s).abs();
     };
 
     Ok(total_distance)
 }
 
+// Similarity score: each number from the first list times its count in the second list
 fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let count = reader.lines().count();
-    Ok(count as i64)
+    let (first, second) = read_lists(reader)?;
+
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+    for number in second {
+        *counts.entry(number).or_insert(0) += 1;
+    }
+
+    Ok(first.iter().map(|number| number * counts.get(number).unwrap_or(&0)).sum())
 }
 
 fn part1_result() -> Result<()> {
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
-    println!("Result = {}", result);
+    run_on_day_input(day!(), part1)?;
     Ok(())
 }
 
 fn part2_result() -> Result<()> {
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
-    println!("Result = {}", result);
+    run_on_day_input(day!(), part2)?;
     Ok(())
 }
 
@@ -59,6 +66,7 @@ fn main() {
 #[cfg(test)]
 mod part1_tests {
     use super::*;
+    use std::io::BufReader;
     #[test]
     fn part1_example() {
         const INPUT: &str = indoc! {"
@@ -73,6 +81,21 @@ mod part1_tests {
         assert_eq!(11i64, part1(BufReader::new(INPUT.as_bytes())).unwrap());
     }
 
+    #[test]
+    fn part1_whitespace() {
+        const INPUT: &str = "3 4\n4\t3\n  2     5  \n";
+
+        assert_eq!(3i64, part1(BufReader::new(INPUT.as_bytes())).unwrap());
+    }
+
+    #[test]
+    fn part1_invalid_line() {
+        assert_eq!(
+            "Line 2: expected two numbers, got 3",
+            part1(BufReader::new("3   4\n1 2 3\n".as_bytes())).unwrap_err().to_string()
+        );
+    }
+
     #[test]
     fn part1_final() {
         part1_result().unwrap();
@@ -82,14 +105,29 @@ mod part1_tests {
 #[cfg(test)]
 mod part2_tests {
     use super::*;
+    use std::io::BufReader;
 
     #[test]
     fn part2_example() {
+        const INPUT: &str = indoc! {"
+            3   4
+            4   3
+            2   5
+            1   3
+            3   9
+            3   3
+        "};
+
+        assert_eq!(31i64, part2(BufReader::new(INPUT.as_bytes())).unwrap());
+    }
+
+    #[test]
+    fn part2_no_matches() {
         const INPUT: &str = indoc! {"
             1   2
         "};
 
-        assert_eq!(1i64, part2(BufReader::new(INPUT.as_bytes())).unwrap());
+        assert_eq!(0i64, part2(BufReader::new(INPUT.as_bytes())).unwrap());
     }
 
     #[test]
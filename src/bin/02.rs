@@ -1,89 +1,174 @@
 use anyhow::*;
+use std::fmt::{Display, Formatter};
 use std::io::BufRead;
+use std::ops::RangeInclusive;
+use itertools::Itertools;
 use advent_of_code2024_rust::{run_on_day_input, day};
 
 fn read_reports<R: BufRead>(reader: R) -> Result<Vec<Vec<i64>>> {
-    let reports: Vec<Vec<i64>> = reader.lines()
-        .flatten()
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let level = line.split(" ")
-                .map(|x| x.parse::<i64>().unwrap())
-                .collect();
-            level
-        })
-        .collect();
+    let mut reports = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let report = line.split_whitespace()
+            .map(|x| x.parse::<i64>().map_err(|_| anyhow!("Line {}: invalid level \"{}\"", index + 1, x)))
+            .collect::<Result<Vec<i64>>>()?;
+        reports.push(report);
+    }
     Ok(reports)
 }
 
-// Number of safe levels
-fn part1<R: BufRead>(reader: R) -> Result<usize> {
-    let reports: Vec<Vec<i64>> = read_reports(reader)?;
-
-    fn is_safe(report: &Vec<i64>) -> bool {
-        if report.len() <= 1 { return true; }
-        let sign = match report[1] - report[0] {
-            0 => return false,
-            diff if diff > 0 => 1,
-            _ => -1,
-        };
-        for i in 1..report.len() {
-            let diff = report[i] - report[i - 1];
-            if diff * sign <= 0 { return false; }
-            let abs = diff.abs();
-            if !((abs >= 1) && (abs <= 3)) { return false; }
+#[derive(Debug, PartialEq)]
+enum Problem {
+    Unchanged,
+    DirectionChanged,
+    StepOutOfRange(i64),
+}
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Safe,
+    // Indexes of the removed levels
+    Dampened(Vec<usize>),
+    // The first level that breaks the undampened report
+    Unsafe { index: usize, problem: Problem },
+}
+
+impl Verdict {
+    fn is_safe(&self) -> bool {
+        !matches!(self, Verdict::Unsafe { .. })
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Safe => write!(f, "safe"),
+            Verdict::Dampened(removed) => write!(f, "safe without level {}", removed.iter().join(", ")),
+            Verdict::Unsafe { index, problem: Problem::Unchanged } =>
+                write!(f, "unsafe: level {} doesn't change", index),
+            Verdict::Unsafe { index, problem: Problem::DirectionChanged } =>
+                write!(f, "unsafe: level {} changes direction", index),
+            Verdict::Unsafe { index, problem: Problem::StepOutOfRange(step) } =>
+                write!(f, "unsafe: level {} changes by {}", index, step),
         }
-        true
     }
+}
 
-    Ok(reports.iter().filter(|level| is_safe(level)).count())
+struct Dampener {
+    max_removals: usize,
+    // Allowed absolute difference between adjacent levels
+    steps: RangeInclusive<i64>,
 }
 
-fn part2<R: BufRead>(reader: R) -> Result<usize> {
-    let reports: Vec<Vec<i64>> = read_reports(reader)?;
-
-    fn unsafe_level_index(report: &Vec<i64>) -> i32 {
-        if report.len() <= 1 { return -1; }
-        let sign = match report[1] - report[0] {
-            0 => return 1,
-            diff if diff > 0 => 1,
-            _ => -1,
-        };
-        for i in 1..report.len() {
-            let diff = report[i] - report[i - 1];
-            if diff * sign <= 0 { return i as i32; }
-            let abs = diff.abs();
-            if !((abs >= 1) && (abs <= 3)) { return i as i32; }
-        }
-        -1
+impl Dampener {
+    fn part1() -> Dampener {
+        Dampener { max_removals: 0, steps: 1..=3 }
+    }
+
+    fn part2() -> Dampener {
+        Dampener { max_removals: 1, ..Dampener::part1() }
     }
 
-    fn is_safe_with_dumper(report: &Vec<i64>) -> bool {
-        let unsafe_level = unsafe_level_index(report);
-        if unsafe_level < 0 { return true; }
+    fn analyze(&self, report: &[i64]) -> Result<Verdict> {
+        let removed = [1, -1].iter()
+            .filter_map(|&sign| self.removals(report, sign))
+            .min_by_key(|removed| removed.len());
+        Ok(match removed {
+            Some(removed) if removed.is_empty() => Verdict::Safe,
+            Some(removed) => Verdict::Dampened(removed),
+            None => {
+                let (index, problem) = self.first_problem(report)
+                    .ok_or_else(|| anyhow!("Report {} can't be dampened but has no problem", report.iter().join(" ")))?;
+                Verdict::Unsafe { index, problem }
+            }
+        })
+    }
 
-        fn remove_from_vector<T: Clone>(vec: &Vec<T>, index: usize) -> Vec<T> {
-            let mut new_vec = vec.clone();
-            new_vec.remove(index);
-            new_vec
+    // Fewest removed levels that make the report monotonic in the sign direction, O(n * max_removals)
+    fn removals(&self, report: &[i64], sign: i64) -> Option<Vec<usize>> {
+        let n = report.len();
+        if n == 0 {
+            return Some(Vec::new());
         }
 
-        if unsafe_level_index(&remove_from_vector(report, unsafe_level as usize)) < 0 {
-            return true;
+        // Fewest removals with level i kept as the last one so far, and the previous kept level
+        let mut removals: Vec<usize> = vec![usize::MAX; n];
+        let mut previous: Vec<Option<usize>> = vec![None; n];
+        for i in 0..n {
+            if i <= self.max_removals {
+                removals[i] = i;
+            }
+            // Nearest kept level first, so ties remove the earlier of two equal candidates
+            for j in (i.saturating_sub(self.max_removals + 1)..i).rev() {
+                if removals[j] == usize::MAX || !self.steps.contains(&((report[i] - report[j]) * sign)) {
+                    continue;
+                }
+                let count = removals[j] + (i - j - 1);
+                if count < removals[i] {
+                    removals[i] = count;
+                    previous[i] = Some(j);
+                }
+            }
+            if removals[i] > self.max_removals {
+                removals[i] = usize::MAX;
+            }
         }
 
-        if unsafe_level_index(&remove_from_vector(report, (unsafe_level - 1) as usize)) < 0 {
-            return true;
-        }
+        let last = (n.saturating_sub(self.max_removals + 1)..n)
+            .filter(|&i| removals[i] != usize::MAX && removals[i] + (n - 1 - i) <= self.max_removals)
+            .min_by_key(|&i| removals[i] + (n - 1 - i))?;
 
-        if unsafe_level == 2 && unsafe_level_index(&remove_from_vector(report, (unsafe_level - 2) as usize)) < 0 {
-            return true;
+        let mut kept = vec![false; n];
+        let mut current = Some(last);
+        while let Some(i) = current {
+            kept[i] = true;
+            current = previous[i];
         }
+        Some((0..n).filter(|&i| !kept[i]).collect())
+    }
 
-        false
+    // Index of the first level that breaks the report and why, None when nothing does
+    fn first_problem(&self, report: &[i64]) -> Option<(usize, Problem)> {
+        let mut direction = 0;
+        for i in 1..report.len() {
+            let diff = report[i] - report[i - 1];
+            let problem = if diff == 0 && !self.steps.contains(&0) {
+                Problem::Unchanged
+            } else if diff.signum() * direction < 0 {
+                Problem::DirectionChanged
+            } else if !self.steps.contains(&diff.abs()) {
+                Problem::StepOutOfRange(diff)
+            } else {
+                if direction == 0 {
+                    direction = diff.signum();
+                }
+                continue;
+            };
+            return Some((i, problem));
+        }
+        None
     }
+}
 
-    Ok(reports.iter().filter(|level| is_safe_with_dumper(level)).count())
+fn analyze_reports<R: BufRead>(reader: R, dampener: &Dampener) -> Result<Vec<(Vec<i64>, Verdict)>> {
+    read_reports(reader)?.into_iter()
+        .map(|report| {
+            let verdict = dampener.analyze(&report)?;
+            Ok((report, verdict))
+        })
+        .collect()
+}
+
+// Number of safe levels
+fn part1<R: BufRead>(reader: R) -> Result<usize> {
+    Ok(analyze_reports(reader, &Dampener::part1())?.iter().filter(|(_, verdict)| verdict.is_safe()).count())
+}
+
+fn part2<R: BufRead>(reader: R) -> Result<usize> {
+    Ok(analyze_reports(reader, &Dampener::part2())?.iter().filter(|(_, verdict)| verdict.is_safe()).count())
 }
 
 //noinspection DuplicatedCode
@@ -102,9 +187,70 @@ fn main() {
     part2_result().unwrap();
 }
 
+#[cfg(test)]
+mod dampener_tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    #[test]
+    fn test_diagnostics() {
+        const INPUT: &str = indoc! {"
+            7 6 4 2 1
+            1 2 7 8 9
+            9 7 6 2 1
+            1 3 2 4 5
+            8 6 4 4 1
+            1 3 6 7 9
+        "};
+
+        let verdicts: Vec<String> = analyze_reports(BufReader::new(INPUT.as_bytes()), &Dampener::part2()).unwrap()
+            .iter()
+            .map(|(_, verdict)| verdict.to_string())
+            .collect();
+        assert_eq!(vec![
+            "safe",
+            "unsafe: level 2 changes by 5",
+            "unsafe: level 3 changes by -4",
+            "safe without level 1",
+            "safe without level 2",
+            "safe",
+        ], verdicts);
+    }
+
+    #[test]
+    fn test_removals() {
+        let dampener = Dampener::part2();
+        assert_eq!(Verdict::Dampened(vec![0]), dampener.analyze(&[3, 0, 1, 2]).unwrap());
+        assert_eq!(Verdict::Dampened(vec![4]), dampener.analyze(&[1, 2, 3, 4, 9]).unwrap());
+        assert_eq!(Verdict::Unsafe { index: 2, problem: Problem::DirectionChanged }, dampener.analyze(&[1, 2, 1, 0, 5]).unwrap());
+        assert_eq!(Verdict::Safe, dampener.analyze(&[]).unwrap());
+        assert_eq!(Verdict::Safe, dampener.analyze(&[5]).unwrap());
+    }
+
+    #[test]
+    fn test_configuration() {
+        let dampener = Dampener { max_removals: 2, steps: 1..=3 };
+        assert_eq!(Verdict::Dampened(vec![1, 3]), dampener.analyze(&[1, 9, 2, 0, 3]).unwrap());
+        assert!(!dampener.analyze(&[1, 9, 2, 0, 3, 8]).unwrap().is_safe());
+
+        let wide = Dampener { max_removals: 0, steps: 0..=5 };
+        assert_eq!(Verdict::Safe, wide.analyze(&[1, 1, 6, 10]).unwrap());
+        assert_eq!(Verdict::Unsafe { index: 2, problem: Problem::StepOutOfRange(6) }, wide.analyze(&[1, 1, 7]).unwrap());
+    }
+
+    #[test]
+    fn test_linear_time() {
+        let report: Vec<i64> = (0..1_000_000).map(|i| i * 2).chain([0]).collect();
+        assert_eq!(Verdict::Dampened(vec![1_000_000]), Dampener::part2().analyze(&report).unwrap());
+    }
+}
+
 #[cfg(test)]
 mod part1_tests {
     use super::*;
+    use indoc::indoc;
+    use std::io::BufReader;
     #[test]
     fn part1_example() {
         const INPUT: &str = indoc! {"
@@ -128,6 +274,8 @@ mod part1_tests {
 #[cfg(test)]
 mod part2_tests {
     use super::*;
+    use indoc::indoc;
+    use std::io::BufReader;
 
     #[test]
     fn part2_example1() {
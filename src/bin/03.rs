@@ -1,57 +1,182 @@
 use anyhow::*;
 use indoc::indoc;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
 use std::io::{BufRead, BufReader};
-use regex::Regex;
+use regex::bytes::Regex;
+use itertools::Itertools;
 use advent_of_code2024_rust::{day, run_on_day_input};
 
-fn part1<R: BufRead>(mut reader: R) -> Result<i64> {
-    let mut content = String::new();
-    reader.read_to_string(&mut content)?;
+struct State {
+    enabled: bool,
+    total: i64,
+}
+
+type Action = fn(&mut State, &[i64]);
 
-    let r = Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)")?;
-    let result = r.captures_iter(&content)
-        .map(|c| {
-            let a = c[1].parse::<i64>().unwrap();
-            let b = c[2].parse::<i64>().unwrap();
-            a * b
+struct Operation {
+    name: String,
+    arity: usize,
+    // Skipped while the state is disabled
+    gated: bool,
+    action: Action,
+}
+
+struct InstructionSet {
+    operations: Vec<Operation>,
+}
+
+impl InstructionSet {
+    fn new() -> InstructionSet {
+        InstructionSet { operations: Vec::new() }
+    }
+
+    fn register(mut self, name: &str, arity: usize, gated: bool, action: Action) -> InstructionSet {
+        self.operations.push(Operation { name: name.to_string(), arity, gated, action });
+        self
+    }
+
+    fn part1() -> InstructionSet {
+        InstructionSet::new()
+            .register("mul", 2, true, |state, args| state.total += args[0] * args[1])
+    }
+
+    fn part2() -> InstructionSet {
+        InstructionSet::part1()
+            .register("do", 0, false, |state, _| state.enabled = true)
+            .register("don't", 0, false, |state, _| state.enabled = false)
+    }
+
+    fn operation(&self, name: &str) -> Option<&Operation> {
+        self.operations.iter().find(|operation| operation.name == name)
+    }
+
+    // name(1,22,333) for any registered name, arguments have up to three digits
+    fn regex(&self) -> Result<Regex> {
+        let names = self.operations.iter().map(|operation| regex::escape(&operation.name)).join("|");
+        Ok(Regex::new(&format!(r"({})\(((?:[0-9]{{1,3}}(?:,[0-9]{{1,3}})*)?)\)", names))?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Instruction {
+    name: String,
+    args: Vec<i64>,
+    // Byte offset in the input
+    offset: usize,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.name, self.args.iter().join(","))
+    }
+}
+
+// Instructions read line by line, no instruction can span a line break
+struct Tokens<'a, R: BufRead> {
+    reader: R,
+    regex: Regex,
+    instruction_set: &'a InstructionSet,
+    line: Vec<u8>,
+    offset: usize,
+    pending: VecDeque<Instruction>,
+}
+
+impl<'a, R: BufRead> Tokens<'a, R> {
+    fn new(reader: R, instruction_set: &'a InstructionSet) -> Result<Tokens<'a, R>> {
+        Ok(Tokens {
+            reader,
+            regex: instruction_set.regex()?,
+            instruction_set,
+            line: Vec::new(),
+            offset: 0,
+            pending: VecDeque::new(),
         })
-        .sum();
-
-    Ok(result)
-}
-
-fn part2<R: BufRead>(mut reader: R) -> Result<i64> {
-    let mut content = String::new();
-    reader.read_to_string(&mut content)?;
-
-    let r = Regex::new(r"do\(\)|don't\(\)|mul\(([0-9]{1,3}),([0-9]{1,3})\)")?;
-
-    let mut state: bool = true;
-    let result = r.captures_iter(&content)
-        .map(|c| {
-            match &c[0] {
-                "do()" => {
-                    state = true;
-                    0
-                },
-                "don't()" => {
-                    state = false;
-                    0
-                },
-                _ => {
-                    if state {
-                        let a = c[1].parse::<i64>().unwrap();
-                        let b = c[2].parse::<i64>().unwrap();
-                        a * b
-                    } else {
-                        0
-                    }
+    }
+
+    fn tokenize_line(&mut self) {
+        for captures in self.regex.captures_iter(&self.line) {
+            let name = String::from_utf8_lossy(&captures[1]).into_owned();
+            let args: Vec<i64> = captures[2].split(|&b| b == b',')
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| arg.iter().fold(0, |acc, &digit| acc * 10 + (digit - b'0') as i64))
+                .collect();
+            // Wrong number of arguments makes it just more corrupted memory
+            if self.instruction_set.operation(&name).is_some_and(|operation| operation.arity == args.len()) {
+                let offset = self.offset + captures.get(0).unwrap().start();
+                self.pending.push_back(Instruction { name, args, offset });
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Tokens<'_, R> {
+    type Item = Result<Instruction>;
+
+    fn next(&mut self) -> Option<Result<Instruction>> {
+        loop {
+            if let Some(instruction) = self.pending.pop_front() {
+                return Some(Ok(instruction));
+            }
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Result::Ok(0) => return None,
+                Result::Ok(size) => {
+                    self.tokenize_line();
+                    self.offset += size;
                 }
+                Err(error) => return Some(Err(error.into())),
             }
-        })
-        .sum();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Step {
+    instruction: Instruction,
+    executed: bool,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} {}", self.instruction.offset, self.instruction,
+               if self.executed { "executed" } else { "skipped" })
+    }
+}
 
-    Ok(result)
+struct Execution {
+    total: i64,
+    steps: Vec<Step>,
+}
+
+fn execute<R: BufRead>(reader: R, instruction_set: &InstructionSet) -> Result<Execution> {
+    let mut state = State { enabled: true, total: 0 };
+    let mut steps = Vec::new();
+    for instruction in Tokens::new(reader, instruction_set)? {
+        let instruction = instruction?;
+        let operation = instruction_set.operation(&instruction.name).unwrap();
+        let executed = state.enabled || !operation.gated;
+        if executed {
+            (operation.action)(&mut state, &instruction.args);
+        }
+        steps.push(Step { instruction, executed });
+    }
+    Ok(Execution { total: state.total, steps })
+}
+
+fn part1<R: BufRead>(reader: R) -> Result<i64> {
+    Ok(execute(reader, &InstructionSet::part1())?.total)
+}
+
+fn part2<R: BufRead>(reader: R) -> Result<i64> {
+    Ok(execute(reader, &InstructionSet::part2())?.total)
+}
+
+// How many instructions the part 2 rules executed and skipped
+fn part2_steps<R: BufRead>(reader: R) -> Result<String> {
+    let execution = execute(reader, &InstructionSet::part2())?;
+    let executed = execution.steps.iter().filter(|step| step.executed).count();
+    Ok(format!("{} instructions executed, {} skipped", executed, execution.steps.len() - executed))
 }
 
 fn part1_result() -> Result<()> {
@@ -61,6 +186,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()>  {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), part2_steps)?;
     Ok(())
 }
 
@@ -69,6 +195,57 @@ fn main() {
     part2_result().unwrap();
 }
 
+#[cfg(test)]
+mod interpreter_tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        const INPUT: &str = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)\n+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+        let execution = execute(BufReader::new(INPUT.as_bytes()), &InstructionSet::part2()).unwrap();
+        assert_eq!(48, execution.total);
+        assert_eq!(
+            vec![
+                "1: mul(2,4) executed",
+                "20: don't() executed",
+                "28: mul(5,5) skipped",
+                "49: mul(11,8) skipped",
+            ],
+            execution.steps.iter().take(4).map(|step| step.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["60: do() executed", "65: mul(8,5) executed"],
+            execution.steps.iter().skip(4).map(|step| step.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "4 instructions executed, 2 skipped",
+            part2_steps(BufReader::new(INPUT.as_bytes())).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_registered_operation() {
+        let instruction_set = InstructionSet::part2()
+            .register("add", 2, true, |state, args| state.total += args[0] + args[1])
+            .register("reset", 0, false, |state, _| state.total = 0);
+
+        const INPUT: &str = "mul(2,3)add(1,2,3)add(4,5)reset()don't()add(1,1)do()add(7,100)mul(1)";
+        let execution = execute(BufReader::new(INPUT.as_bytes()), &instruction_set).unwrap();
+        assert_eq!(107, execution.total);
+        assert_eq!(
+            vec!["mul", "add", "reset", "don't", "add", "do", "add"],
+            execution.steps.iter().map(|step| step.instruction.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let input: &[u8] = b"\xffmul(2,4)\xfe";
+        assert_eq!(8, part1(BufReader::new(input)).unwrap());
+    }
+}
+
 #[cfg(test)]
 mod part1_tests {
     use super::*;
@@ -1,5 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use advent_of_code2024_rust::{day, run_on_day_input};
+use advent_of_code2024_rust::graph::topological_sort;
 use anyhow::*;
 use indoc::indoc;
 use std::io::{BufRead, BufReader};
@@ -11,34 +12,48 @@ struct Input {
     updates: Vec<LinkedHashSet<i32>>
 }
 
-fn parse_input<R: BufRead>(reader: R) -> Input {
-    let lines: Vec<String> = reader.lines()
-        .flatten()
-        .collect();
-    let mut split = lines.split(|line| line.is_empty());
-    let rules_strings = split.next().unwrap();
-    let updates_strings = split.next().unwrap();
-
-    let rules: Vec<(i32, i32)> = rules_strings.iter().map(|line| {
-        let mut split = line.split('|');
-        let before = split.next().unwrap().parse::<i32>().unwrap();
-        let after = split.next().unwrap().parse::<i32>().unwrap();
-        assert_ne!(before, after);
-        (before, after)
-    }).collect();
-
-    let updates: Vec<LinkedHashSet<i32>> = updates_strings.iter()
-        .map(|line| {
-            let pages: Vec<i32> = line.split(',').map(|page_str| page_str.parse::<i32>().unwrap()).collect();
-            let mut pages_set = LinkedHashSet::new();
-            pages_set.extend(pages.clone());
-            pages_set
-        }).collect();
-
-    Input {
+fn parse_page(page: &str, line_number: usize) -> Result<i32> {
+    page.trim().parse::<i32>()
+        .map_err(|_| anyhow!("Line {}: invalid page \"{}\"", line_number, page))
+}
+
+fn parse_input<R: BufRead>(reader: R) -> Result<Input> {
+    let mut rules = Vec::new();
+    let mut updates = Vec::new();
+    let mut reading_updates = false;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.is_empty() {
+            reading_updates = true;
+            continue;
+        }
+
+        if !reading_updates {
+            let (before, after) = line.split_once('|')
+                .ok_or_else(|| anyhow!("Line {}: expected rule \"X|Y\", got \"{}\"", line_number, line))?;
+            let (before, after) = (parse_page(before, line_number)?, parse_page(after, line_number)?);
+            if before == after {
+                bail!("Line {}: rule orders page {} against itself", line_number, before);
+            }
+            rules.push((before, after));
+        } else {
+            let mut pages = LinkedHashSet::new();
+            for page in line.split(',') {
+                let page = parse_page(page, line_number)?;
+                if !pages.insert(page) {
+                    bail!("Line {}: page {} repeats in the update", line_number, page);
+                }
+            }
+            updates.push(pages);
+        }
+    }
+
+    Ok(Input {
         rules,
         updates
-    }
+    })
 }
 
 // Function to extract working rules from a given rule
@@ -49,98 +64,85 @@ fn get_working_rules(update: &LinkedHashSet<i32>, rules: &[(i32, i32)]) -> Vec<(
         .collect()
 }
 
+#[derive(Debug, PartialEq)]
+struct Violation {
+    rule: (i32, i32),
+    // Positions of the rule's before and after pages in the update
+    positions: (usize, usize),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rule {}|{} is violated: {} at position {} comes after {} at position {}",
+            self.rule.0, self.rule.1, self.rule.0, self.positions.0, self.rule.1, self.positions.1
+        )
+    }
+}
+
+// First rule, by position of its later page, that the update breaks
+fn find_violation(update: &LinkedHashSet<i32>, rules: &[(i32, i32)]) -> Option<Violation> {
+    let positions: Vec<i32> = update.iter().copied().collect();
+    let position = |page: i32| positions.iter().position(|&p| p == page).unwrap();
+    get_working_rules(update, rules).into_iter()
+        .map(|rule| Violation { rule, positions: (position(rule.0), position(rule.1)) })
+        .filter(|violation| violation.positions.0 > violation.positions.1)
+        .min_by_key(|violation| (violation.positions.0, violation.positions.1))
+}
+
 // Function to check if the rules are in the correct order
 fn is_in_correct_order(update: &LinkedHashSet<i32>, rules: &[(i32, i32)]) -> bool {
-    let mut working_rules = get_working_rules(update, rules);
-    for page in update {
-        if working_rules.iter().any(|(_, after)| after == page) {
-            return false;
-        }
-        working_rules.retain(|(before, _)| before != page);
-    }
-    true
+    find_violation(update, rules).is_none()
 }
 
-//noinspection DuplicatedCode
-fn fix_order(update: &LinkedHashSet<i32>, rules: &[(i32, i32)]) -> Vec<i32> {
-    let working_rules = get_working_rules(update, rules);
-
-    let mut map_after: HashMap<i32, HashSet<i32>> = HashMap::new();
-    let mut map_before: HashMap<i32, HashSet<i32>> = HashMap::new();
-    for (before, after) in working_rules {
-        if map_after.contains_key(&after) {
-            map_after.get_mut(&after).unwrap().insert(before);
-        } else {
-            let mut set = HashSet::new();
-            set.insert(before);
-            map_after.insert(after, set);
-        }
+// Reorders the update to satisfy the rules, whenever several pages are free to go next the one
+// listed earliest in the update is taken
+fn fix_order(update: &LinkedHashSet<i32>, rules: &[(i32, i32)]) -> Result<Vec<i32>> {
+    let pages: Vec<i32> = update.iter().copied().collect();
+    topological_sort(&pages, &get_working_rules(update, rules))
+        .map_err(|cycle| anyhow!("Contradictory rules for update {}: {}", pages.iter().join(","), cycle))
+}
 
-        if map_before.contains_key(&before) {
-            map_before.get_mut(&before).unwrap().insert(after);
-        } else {
-            let mut set = HashSet::new();
-            set.insert(after);
-            map_before.insert(before, set);
-        }
-    }
+fn explain_violations(input: &Input) -> Vec<String> {
+    input.updates.iter()
+        .filter_map(|update| {
+            find_violation(update, &input.rules)
+                .map(|violation| format!("{}: {}", update.iter().join(","), violation))
+        })
+        .collect()
+}
 
-    let mut result: Vec<i32> = vec![];
-    let mut next: HashSet<i32> = update.iter().filter(|page| !map_after.contains_key(page)).cloned().collect();
-    while !next.is_empty() {
-        assert_eq!(next.len(), 1);
-        let page = next.iter().next().unwrap();
-        result.push(*page);
-
-        let option = map_before.get(&page);
-        if option.is_some() {
-            let mut next_set = HashSet::new();
-
-            for after in option.unwrap() {
-                if map_after.contains_key(&after) {
-                    let after_set = map_after.get_mut(&after).unwrap();
-                    after_set.remove(&page);
-                    if after_set.is_empty() {
-                        next_set.insert(*after);
-                    }
-                }
-            }
-            next = next_set;
-        } else {
-            break
-        }
+fn middle_page(pages: &[i32]) -> Result<i64> {
+    if pages.len() % 2 != 1 {
+        bail!("Update {} has no middle page", pages.iter().join(","));
     }
-
-    result
+    Ok(pages[pages.len() / 2] as i64)
 }
 
 //noinspection DuplicatedCode
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let input = parse_input(reader);
+    let input = parse_input(reader)?;
 
-    Ok(input.updates.iter()
+    input.updates.iter()
         .filter(|update| is_in_correct_order(update, &input.rules))
-        .map(|update| {
-            let len = update.len();
-            assert_eq!(len % 2, 1);
-            *(update.iter().clone().collect::<Vec<&i32>>()[len / 2]) as i64
-        }).
-        sum())
+        .map(|update| middle_page(&update.iter().copied().collect::<Vec<i32>>()))
+        .sum()
 }
 
 //noinspection DuplicatedCode
 fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let input = parse_input(reader);
+    let input = parse_input(reader)?;
 
-    Ok(input.updates.iter()
+    input.updates.iter()
         .filter(|update| !is_in_correct_order(update, &input.rules))
-        .map(|update| fix_order(update, &input.rules))
-        .map(|fixed_update| {
-            let len = fixed_update.len();
-            assert_eq!(len % 2, 1);
-            *(fixed_update.iter().clone().collect::<Vec<&i32>>()[len / 2]) as i64
-        }).
-        sum())
+        .map(|update| middle_page(&fix_order(update, &input.rules)?))
+        .sum()
+}
+
+// The rule each incorrectly ordered update breaks first, one update per line
+fn part2_violations<R: BufRead>(reader: R) -> Result<String> {
+    Ok(explain_violations(&parse_input(reader)?).join("\n"))
 }
 
 fn part1_result() -> Result<()> {
@@ -150,6 +152,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), part2_violations)?;
     Ok(())
 }
 
@@ -158,6 +161,82 @@ fn main() {
     part2_result().unwrap();
 }
 
+//noinspection SpellCheckingInspection
+#[cfg(test)]
+mod ordering_tests {
+    use super::*;
+
+    const INPUT: &str = indoc! {"
+        47|53
+        97|13
+        97|61
+        97|47
+        75|29
+        61|13
+        75|53
+        29|13
+        97|29
+        53|29
+        61|53
+        97|53
+        61|29
+        47|13
+        75|47
+        97|75
+        47|61
+        75|61
+        47|29
+        75|13
+        53|13
+
+        75,47,61,53,29
+        97,61,53,29,13
+        75,29,13
+        75,97,47,61,53
+        61,13,29
+        97,13,75,29,47
+    "};
+
+    fn update(pages: &[i32]) -> LinkedHashSet<i32> {
+        pages.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_explain_violations() {
+        let input = parse_input(BufReader::new(INPUT.as_bytes())).unwrap();
+        assert_eq!(explain_violations(&input), vec![
+            "75,97,47,61,53: rule 97|75 is violated: 97 at position 1 comes after 75 at position 0",
+            "61,13,29: rule 29|13 is violated: 29 at position 2 comes after 13 at position 1",
+            "97,13,75,29,47: rule 75|13 is violated: 75 at position 2 comes after 13 at position 1",
+        ]);
+        assert_eq!(3, part2_violations(BufReader::new(INPUT.as_bytes())).unwrap().lines().count());
+    }
+
+    #[test]
+    fn test_fix_order_is_deterministic() {
+        // 3 and 2 wait for 1, so 4 is the earliest page free to go first
+        assert_eq!(vec![4, 1, 3, 2], fix_order(&update(&[3, 2, 4, 1]), &[(1, 2), (1, 3)]).unwrap());
+        // Ties follow the update, not the page numbers
+        assert_eq!(vec![5, 4, 9, 7], fix_order(&update(&[5, 4, 9, 7]), &[]).unwrap());
+    }
+
+    #[test]
+    fn test_fix_order_cycle() {
+        assert_eq!(
+            "Contradictory rules for update 1,2,3: Cycle: 1 -> 2 -> 3 -> 1",
+            fix_order(&update(&[1, 2, 3]), &[(1, 2), (2, 3), (3, 1)]).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input: &str| parse_input(BufReader::new(input.as_bytes())).err().unwrap().to_string();
+        assert_eq!("Line 2: expected rule \"X|Y\", got \"47-53\"", error("1|2\n47-53\n"));
+        assert_eq!("Line 3: invalid page \"x\"", error("1|2\n\n1,x\n"));
+        assert_eq!("Line 1: rule orders page 4 against itself", error("4|4\n"));
+    }
+}
+
 //noinspection SpellCheckingInspection
 #[cfg(test)]
 mod part1_tests {
@@ -24,6 +24,34 @@ impl Direction {
             Direction::LEFT => Direction::UP,
         }
     }
+
+    fn turn_left(&self) -> Direction {
+        self.turn_right().turn_right().turn_right()
+    }
+
+    fn reverse(&self) -> Direction {
+        self.turn_right().turn_right()
+    }
+}
+
+// What a guard does in front of a wall
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+enum TurnPolicy {
+    Right,
+    #[allow(dead_code)]
+    Left,
+    #[allow(dead_code)]
+    Reverse,
+}
+
+impl TurnPolicy {
+    fn turn(&self, direction: Direction) -> Direction {
+        match self {
+            TurnPolicy::Right => direction.turn_right(),
+            TurnPolicy::Left => direction.turn_left(),
+            TurnPolicy::Reverse => direction.reverse(),
+        }
+    }
 }
 
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
@@ -97,13 +125,13 @@ fn step_forward_coordinate(position: &Position, map: &Map) -> Option<Coordinate>
     }
 }
 
-fn move_guard(position: &Position, map: &Map, additional_wall: Option<Coordinate>) -> Option<Position> {
+fn move_guard(position: &Position, map: &Map, additional_wall: Option<Coordinate>, policy: TurnPolicy) -> Option<Position> {
     let next_coordinate = step_forward_coordinate(position, map)?;
 
     if map.get_cell(next_coordinate) == Wall ||
         additional_wall.map_or(false, |coordinate| coordinate == next_coordinate) {
         return Some(Position {
-            direction: position.direction.turn_right(),
+            direction: policy.turn(position.direction),
             coordinate: position.coordinate,
         });
     }
@@ -145,42 +173,58 @@ fn visited_map(map: &Map, visited: &LinkedHashSet<Position>) -> String {
     }).join("\n")
 }
 
-fn read_input<R: BufRead>(reader: R) -> Result<(Coordinate, Map)> {
-    let mut start: Option<Coordinate> = None;
-    let map: Vec<Vec<Cell>> = reader.lines()
-        .flatten()
-        .enumerate()
-        .filter(|(_, line)| !line.is_empty())
-        .map(|(y, line)| {
-            line.char_indices().map(|(x, c)| {
-                match c {
-                    '.' => Empty,
-                    '#' => Wall,
-                    '^' => {
-                        start = Some(Coordinate { x, y });
-                        Empty
-                    }
-                    _ => {
-                        panic!("Unknown character: {}", c);
-                    }
+// The starting position of the only guard and the map, guard cells are empty
+fn read_input<R: BufRead>(reader: R) -> Result<(Position, Map)> {
+    let mut start: Option<Position> = None;
+    let mut map: Vec<Vec<Cell>> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let y = map.len();
+        let mut row = Vec::new();
+        for (x, c) in line.chars().enumerate() {
+            let direction = match c {
+                '.' => None,
+                '#' => {
+                    row.push(Wall);
+                    continue;
                 }
-            }).collect()
-        })
-        .collect();
+                '^' => Some(Direction::UP),
+                '>' => Some(Direction::RIGHT),
+                'v' => Some(Direction::DOWN),
+                '<' => Some(Direction::LEFT),
+                _ => bail!("Unknown character '{}' at row {}, column {}", c, y + 1, x + 1),
+            };
+            if let Some(direction) = direction {
+                if let Some(other) = start {
+                    bail!(
+                        "Several guards: at row {}, column {} and at row {}, column {}",
+                        other.coordinate.y + 1, other.coordinate.x + 1, y + 1, x + 1
+                    );
+                }
+                start = Some(Position { direction, coordinate: Coordinate { x, y } });
+            }
+            row.push(Empty);
+        }
+        if let Some(first) = map.first() {
+            if row.len() != first.len() {
+                bail!("Row {} has {} cells, expected {}", y + 1, row.len(), first.len());
+            }
+        }
+        map.push(row);
+    }
 
-    let map = if map.is_empty() || map[0].is_empty() {
-        return Err(anyhow!("Map is empty"));
-    } else {
-        let x_size = map[0].len();
-        let y_size = map.len();
+    if map.is_empty() {
+        bail!("Map is empty");
+    }
+    let start = start.ok_or_else(|| anyhow!("No guard on the map"))?;
+    let x_size = map[0].len();
+    let y_size = map.len();
 
-        Map {
-            map,
-            x_size,
-            y_size,
-        }
-    };
-    Ok((start.unwrap(), map))
+    Ok((start, Map { map, x_size, y_size }))
 }
 
 fn build_trace(position: &Position, map: &Map, additional_wall: Option<Coordinate>) -> (bool, LinkedHashSet<Position>) {
@@ -188,11 +232,13 @@ fn build_trace(position: &Position, map: &Map, additional_wall: Option<Coordinat
     let mut cur_position = position.clone();
 
     loop {
-        if !trace.insert(cur_position) {
+        // Inserting a present value would move it to the back and break the order of the trace
+        if trace.contains(&cur_position) {
             return (true, trace);
         }
+        trace.insert(cur_position);
 
-        let next_position = move_guard(&cur_position, &map, additional_wall);
+        let next_position = move_guard(&cur_position, map, additional_wall, TurnPolicy::Right);
         if next_position.is_none() {
             return (false, trace);
         }
@@ -201,42 +247,262 @@ fn build_trace(position: &Position, map: &Map, additional_wall: Option<Coordinat
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+struct Guard {
+    position: Position,
+    policy: TurnPolicy,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Outcome {
+    Patrolling,
+    // Moves made before leaving the map or coming back to a known position
+    Exited { steps: usize },
+    Looped { steps: usize },
+}
+
+struct GuardState {
+    guard: Guard,
+    outcome: Outcome,
+    steps: usize,
+    visited: HashSet<Position>,
+}
+
+// Guards move in turns of one step each, they don't block each other
+struct Patrol<'a> {
+    map: &'a Map,
+    additional_wall: Option<Coordinate>,
+    guards: Vec<GuardState>,
+}
+
+impl<'a> Patrol<'a> {
+    fn new(map: &'a Map, guards: Vec<Guard>) -> Patrol<'a> {
+        let guards = guards.into_iter()
+            .map(|guard| GuardState {
+                guard,
+                outcome: Outcome::Patrolling,
+                steps: 0,
+                visited: HashSet::from([guard.position]),
+            })
+            .collect();
+        Patrol { map, additional_wall: None, guards }
+    }
+
+    fn with_wall(self, coordinate: Coordinate) -> Patrol<'a> {
+        Patrol { additional_wall: Some(coordinate), ..self }
+    }
+
+    // Moves every patrolling guard once, returns the guards that moved with their new positions
+    fn step(&mut self) -> Vec<(usize, Position)> {
+        let mut moved = Vec::new();
+        for (index, state) in self.guards.iter_mut().enumerate() {
+            if state.outcome != Outcome::Patrolling {
+                continue;
+            }
+            let Some(next) = move_guard(&state.guard.position, self.map, self.additional_wall, state.guard.policy) else {
+                state.outcome = Outcome::Exited { steps: state.steps };
+                continue;
+            };
+            state.steps += 1;
+            state.guard.position = next;
+            if !state.visited.insert(next) {
+                state.outcome = Outcome::Looped { steps: state.steps };
+            }
+            moved.push((index, next));
+        }
+        moved
+    }
+
+    fn run(&mut self) -> Vec<Outcome> {
+        while self.next().is_some() {}
+        self.outcomes()
+    }
+
+    fn outcomes(&self) -> Vec<Outcome> {
+        self.guards.iter().map(|state| state.outcome).collect()
+    }
+
+    fn visited_coordinates(&self, guard: usize) -> HashSet<Coordinate> {
+        self.guards[guard].visited.iter().map(|position| position.coordinate).collect()
+    }
+}
+
+impl Iterator for Patrol<'_> {
+    type Item = Vec<(usize, Position)>;
+
+    fn next(&mut self) -> Option<Vec<(usize, Position)>> {
+        if self.guards.iter().all(|state| state.outcome != Outcome::Patrolling) {
+            return None;
+        }
+        Some(self.step())
+    }
+}
+
 //noinspection DuplicatedCode
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
     let (start, map) = read_input(reader)?;
-    let start_position = Position {
-        direction: Direction::UP,
-        coordinate: start,
-    };
+    let guard = Guard { position: start, policy: TurnPolicy::Right };
 
-    let (_, trace) = build_trace(&start_position, &map, None);
+    let mut patrol = Patrol::new(&map, vec![guard]);
+    patrol.run();
 
-    let visited_coordinates = trace.iter()
-        .map(|position: &Position| position.coordinate)
-        .collect::<HashSet<Coordinate>>();
+    Ok(patrol.visited_coordinates(0).len() as i64)
+}
 
-    Ok(visited_coordinates.len() as i64)
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum LoopDetector {
+    // Replays the whole patrol for every candidate obstacle
+    #[allow(dead_code)]
+    Trace,
+    // Jumps from wall to wall starting right before the candidate obstacle
+    JumpTable,
 }
 
-//noinspection DuplicatedCode
-fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let (start, map) = read_input(reader)?;
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(size: usize) -> BitSet {
+        BitSet { words: vec![0; size.div_ceil(64)] }
+    }
 
-    let starting_position = Position {
-        direction: Direction::UP,
-        coordinate: start,
-    };
+    // false if the bit was already set
+    fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1u64 << (index % 64));
+        let inserted = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        inserted
+    }
 
-    let (_, trace) = build_trace(&starting_position, &map, None);
+    fn clear(&mut self) {
+        self.words.fill(0);
+    }
+}
 
-    Ok(trace.iter()
-        .map(|position: &Position| position.coordinate)
-        .collect::<LinkedHashSet<Coordinate>>().iter()
-        .filter(|&&coordinate| coordinate != start)
-        .filter(|&&coordinate| {
-            build_trace(&starting_position, &map, Some(coordinate)).0
+fn direction_index(direction: Direction) -> usize {
+    match direction {
+        Direction::UP => 0,
+        Direction::RIGHT => 1,
+        Direction::DOWN => 2,
+        Direction::LEFT => 3,
+    }
+}
+
+// Cell where the guard stops before the next wall in each direction, None when it leaves the map
+struct JumpTable {
+    stops: Vec<Option<Coordinate>>,
+    x_size: usize,
+}
+
+impl JumpTable {
+    fn new(map: &Map) -> JumpTable {
+        let mut table = JumpTable { stops: vec![None; map.x_size * map.y_size * 4], x_size: map.x_size };
+        for direction in [Direction::UP, Direction::RIGHT, Direction::DOWN, Direction::LEFT] {
+            // Cells ahead are filled in before the cells behind them
+            let mut coordinates: Vec<Coordinate> = (0..map.y_size)
+                .flat_map(|y| (0..map.x_size).map(move |x| Coordinate { x, y }))
+                .collect();
+            if direction == Direction::DOWN || direction == Direction::RIGHT {
+                coordinates.reverse();
+            }
+            for coordinate in coordinates {
+                let position = Position { direction, coordinate };
+                let stop = match step_forward_coordinate(&position, map) {
+                    None => None,
+                    Some(next) if map.get_cell(next) == Wall => Some(coordinate),
+                    Some(next) => table.stops[table.index(&Position { direction, coordinate: next })],
+                };
+                let index = table.index(&position);
+                table.stops[index] = stop;
+            }
+        }
+        table
+    }
+
+    fn index(&self, position: &Position) -> usize {
+        (position.coordinate.y * self.x_size + position.coordinate.x) * 4 + direction_index(position.direction)
+    }
+
+    // Where the guard stops from the position with an extra obstacle on the map
+    fn stop(&self, position: &Position, obstacle: Coordinate) -> Option<Coordinate> {
+        let Coordinate { x, y } = position.coordinate;
+        let wall_stop = self.stops[self.index(position)];
+        // Distance to the obstacle when it is straight ahead
+        let ahead = match position.direction {
+            Direction::UP if obstacle.x == x && obstacle.y < y => Some(y - obstacle.y),
+            Direction::DOWN if obstacle.x == x && obstacle.y > y => Some(obstacle.y - y),
+            Direction::LEFT if obstacle.y == y && obstacle.x < x => Some(x - obstacle.x),
+            Direction::RIGHT if obstacle.y == y && obstacle.x > x => Some(obstacle.x - x),
+            _ => None,
+        };
+        let Some(distance) = ahead else {
+            return wall_stop;
+        };
+        let wall_distance = wall_stop.map(|stop| stop.x.abs_diff(x) + stop.y.abs_diff(y));
+        if wall_distance.is_some_and(|wall_distance| wall_distance < distance) {
+            return wall_stop;
+        }
+        let steps = distance - 1;
+        Some(match position.direction {
+            Direction::UP => Coordinate { x, y: y - steps },
+            Direction::DOWN => Coordinate { x, y: y + steps },
+            Direction::LEFT => Coordinate { x: x - steps, y },
+            Direction::RIGHT => Coordinate { x: x + steps, y },
         })
-        .count() as i64)
+    }
+
+    fn is_loop(&self, start: &Position, obstacle: Coordinate, visited: &mut BitSet) -> bool {
+        visited.clear();
+        let mut position = *start;
+        loop {
+            let Some(coordinate) = self.stop(&position, obstacle) else {
+                return false;
+            };
+            position = Position { direction: position.direction.turn_right(), coordinate };
+            if !visited.insert(self.index(&position)) {
+                return true;
+            }
+        }
+    }
+}
+
+fn count_loop_obstacles(starting_position: Position, map: &Map, detector: LoopDetector) -> usize {
+    let start = starting_position.coordinate;
+    let (_, trace) = build_trace(&starting_position, map, None);
+
+    match detector {
+        LoopDetector::Trace => trace.iter()
+            .map(|position: &Position| position.coordinate)
+            .collect::<LinkedHashSet<Coordinate>>().iter()
+            .filter(|&&coordinate| coordinate != start)
+            .filter(|&&coordinate| {
+                let guard = Guard { position: starting_position, policy: TurnPolicy::Right };
+                let mut patrol = Patrol::new(map, vec![guard]).with_wall(coordinate);
+                matches!(patrol.run()[0], Outcome::Looped { .. })
+            })
+            .count(),
+        LoopDetector::JumpTable => {
+            let table = JumpTable::new(map);
+            let mut visited = BitSet::new(table.stops.len());
+            let mut tried: HashSet<Coordinate> = HashSet::from([start]);
+            // The path up to the first visit of an obstacle cell doesn't change with the obstacle
+            trace.iter().tuple_windows()
+                .filter(|(_, next)| tried.insert(next.coordinate))
+                .filter(|(before, next)| table.is_loop(before, next.coordinate, &mut visited))
+                .count()
+        }
+    }
+}
+
+fn part2<R: BufRead>(reader: R) -> Result<i64> {
+    part2_ext(reader, LoopDetector::JumpTable)
+}
+
+//noinspection DuplicatedCode
+fn part2_ext<R: BufRead>(reader: R, detector: LoopDetector) -> Result<i64> {
+    let (start, map) = read_input(reader)?;
+    Ok(count_loop_obstacles(start, &map, detector) as i64)
 }
 
 //#region
@@ -265,8 +531,118 @@ mod tests {
     use std::io::BufReader;
     use indoc::indoc;
 
+    mod patrol_tests {
+        use super::*;
+
+        const MAP: &str = indoc! {"
+            ....#.....
+            .........#
+            ..........
+            ..#.......
+            .......#..
+            ..........
+            .#..^.....
+            ........#.
+            #.........
+            ......#...
+        "};
+
+        fn guard(x: usize, y: usize, direction: Direction, policy: TurnPolicy) -> Guard {
+            Guard { position: Position { direction, coordinate: Coordinate { x, y } }, policy }
+        }
+
+        #[test]
+        fn test_policies() {
+            let (start, map) = read_input(BufReader::new(MAP.as_bytes())).unwrap();
+            let mut patrol = Patrol::new(&map, vec![
+                guard(start.coordinate.x, start.coordinate.y, Direction::UP, TurnPolicy::Right),
+                guard(start.coordinate.x, start.coordinate.y, Direction::UP, TurnPolicy::Left),
+                guard(start.coordinate.x, start.coordinate.y, Direction::UP, TurnPolicy::Reverse),
+                guard(5, 3, Direction::LEFT, TurnPolicy::Right),
+            ]);
+            assert_eq!(
+                vec![
+                    Outcome::Exited { steps: 54 },
+                    Outcome::Exited { steps: 10 },
+                    Outcome::Exited { steps: 14 },
+                    Outcome::Exited { steps: 6 },
+                ],
+                patrol.run()
+            );
+            assert_eq!(41, patrol.visited_coordinates(0).len());
+            assert_eq!(9, patrol.visited_coordinates(2).len());
+        }
+
+        #[test]
+        fn test_steps() {
+            let (_, map) = read_input(BufReader::new(MAP.as_bytes())).unwrap();
+            let mut patrol = Patrol::new(&map, vec![
+                guard(4, 1, Direction::UP, TurnPolicy::Right),
+                guard(1, 0, Direction::UP, TurnPolicy::Right),
+            ]);
+
+            let moves: Vec<Vec<(usize, Position)>> = patrol.by_ref().take(2).collect();
+            assert_eq!(
+                vec![(0, Position { direction: Direction::RIGHT, coordinate: Coordinate { x: 4, y: 1 } })],
+                moves[0][..1]
+            );
+            assert_eq!(1, moves[0].len());
+            assert_eq!(
+                vec![(0, Position { direction: Direction::RIGHT, coordinate: Coordinate { x: 5, y: 1 } })],
+                moves[1]
+            );
+            assert_eq!(vec![Outcome::Patrolling, Outcome::Exited { steps: 0 }], patrol.outcomes());
+        }
+
+        #[test]
+        fn test_additional_wall() {
+            let (start, map) = read_input(BufReader::new(MAP.as_bytes())).unwrap();
+            let guard = guard(start.coordinate.x, start.coordinate.y, Direction::UP, TurnPolicy::Right);
+            let mut patrol = Patrol::new(&map, vec![guard]).with_wall(Coordinate { x: 3, y: 6 });
+            assert!(matches!(patrol.run()[0], Outcome::Looped { .. }));
+
+            // Bounces between the wall on top and the new one below
+            let reverse = Guard { policy: TurnPolicy::Reverse, ..guard };
+            let mut patrol = Patrol::new(&map, vec![reverse]).with_wall(Coordinate { x: 4, y: 8 });
+            assert_eq!(vec![Outcome::Looped { steps: 14 }], patrol.run());
+        }
+    }
+
+    mod read_input_tests {
+        use super::*;
+
+        fn error(input: &str) -> String {
+            read_input(BufReader::new(input.as_bytes())).err().unwrap().to_string()
+        }
+
+        #[test]
+        fn test_guard_glyphs() {
+            for (glyph, direction) in [('^', Direction::UP), ('>', Direction::RIGHT), ('v', Direction::DOWN), ('<', Direction::LEFT)] {
+                let (start, map) = read_input(BufReader::new(format!("#..\n.{}.\n", glyph).as_bytes())).unwrap();
+                assert_eq!(Position { direction, coordinate: Coordinate { x: 1, y: 1 } }, start);
+                assert_eq!(Empty, map.get_cell(start.coordinate));
+            }
+        }
+
+        #[test]
+        fn test_errors() {
+            assert_eq!("Unknown character 'x' at row 2, column 3", error("...\n..x\n.^.\n"));
+            assert_eq!("Several guards: at row 1, column 2 and at row 3, column 1", error(".^.\n...\n<..\n"));
+            assert_eq!("Row 2 has 2 cells, expected 3", error(".^.\n..\n"));
+            assert_eq!("No guard on the map", error("...\n"));
+            assert_eq!("Map is empty", error("\n"));
+        }
+
+        #[test]
+        fn test_start_facing_right() {
+            // The guard leaves through the right edge right away
+            assert_eq!(3, part1(BufReader::new("#...\n.>..\n".as_bytes())).unwrap());
+        }
+    }
+
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -293,7 +669,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(5239i64, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -301,9 +677,32 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: i64, input: &str) {
-            assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
+            for detector in [LoopDetector::Trace, LoopDetector::JumpTable] {
+                assert_eq!(expect, part2_ext(BufReader::new(input.as_bytes()), detector).unwrap(), "{:?}", detector);
+            }
+        }
+
+        #[test]
+        fn test_detectors_agree() {
+            let mut seed: u64 = 42;
+            for _ in 0..20 {
+                let mut map = String::new();
+                for y in 0..30 {
+                    for x in 0..30 {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        map.push(if (x, y) == (15, 15) { '^' } else if seed >> 60 < 2 { '#' } else { '.' });
+                    }
+                    map.push('\n');
+                }
+                assert_eq!(
+                    part2_ext(BufReader::new(map.as_bytes()), LoopDetector::Trace).unwrap(),
+                    part2_ext(BufReader::new(map.as_bytes()), LoopDetector::JumpTable).unwrap(),
+                    "{}", map
+                );
+            }
         }
 
         #[test]
@@ -393,7 +792,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!(1753i64, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
@@ -1,106 +1,258 @@
 use core::result::Result::Ok;
 use advent_of_code2024_rust::{day, run_on_day_input};
+use advent_of_code2024_rust::numeric::Integer;
 use anyhow::*;
+use std::fmt::{Display, Formatter};
 use std::io::BufRead;
-use std::num::ParseIntError;
 
-fn can_be_true_sum_mul(result: i64, numbers: &Vec<i64>, len: usize) -> bool {
-    if len == 0 {
-        return result == 0;
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Multiply,
+    Concatenate,
+    #[allow(dead_code)]
+    Subtract,
+    #[allow(dead_code)]
+    Xor,
+}
+
+// Left operands that give `result` for the given right operand
+enum Inverse {
+    Value(Integer),
+    Any,
+    Nothing,
+}
+
+impl Operator {
+    // None when the operator can't take the right operand, an error when the value overflows
+    fn apply(&self, left: &Integer, right: &Integer) -> Result<Option<Integer>> {
+        Ok(match self {
+            Operator::Add => Some(left.add(right)?),
+            Operator::Multiply => Some(left.mul(right)?),
+            Operator::Concatenate if right.is_negative() => None,
+            Operator::Concatenate => Some(left.concat(right)?),
+            Operator::Subtract => Some(left.sub(right)?),
+            Operator::Xor => Some(left.xor(right)),
+        })
     }
 
-    if result <= 0 {
-        return false;
+    // Overflow is an error, the branch might still hold a solution that can't be represented
+    fn invert(&self, result: &Integer, right: &Integer) -> Result<Inverse> {
+        let value = match self {
+            Operator::Add => Some(result.sub(right)?),
+            Operator::Multiply if right.is_zero() => {
+                return Ok(if result.is_zero() { Inverse::Any } else { Inverse::Nothing });
+            }
+            Operator::Multiply => result.div_exact(right)?,
+            Operator::Concatenate if right.is_negative() => None,
+            Operator::Concatenate => {
+                let (prefix, suffix) = result.div_rem(&Integer::pow10(right.to_string().len())?)?;
+                let expected = if result.is_negative() { Integer::zero().sub(right)? } else { right.clone() };
+                // A zero prefix concatenates to a positive number, it can't give a negative result
+                if suffix == expected && !(result.is_negative() && prefix.is_zero()) { Some(prefix) } else { None }
+            }
+            Operator::Subtract => Some(result.add(right)?),
+            Operator::Xor => Some(result.xor(right)),
+        };
+        Ok(value.map(Inverse::Value).unwrap_or(Inverse::Nothing))
     }
 
-    let last = numbers[len - 1];
-    if result % last == 0 {
-        if can_be_true_sum_mul(result / last, numbers, len - 1) {
-            return true;
-        }
+    // Never decreases a positive left operand when the right one is positive
+    fn is_growing(&self) -> bool {
+        matches!(self, Operator::Add | Operator::Multiply | Operator::Concatenate)
     }
 
-    can_be_true_sum_mul(result - last, numbers, len - 1)
+    fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Multiply => "*",
+            Operator::Concatenate => "||",
+            Operator::Subtract => "-",
+            Operator::Xor => "^",
+        }
+    }
 }
 
-fn concatenate(left: i64, right: i64) -> std::result::Result<i64, ParseIntError> {
-    (left.to_string() + &right.to_string()).parse::<i64>()
+struct Solver<'a> {
+    operators: &'a [Operator],
 }
 
-fn can_be_true_sum_mul_concatenation(result: i64, numbers: &Vec<i64>, len: usize) -> bool {
-    if len == 0 {
-        return result == 0;
+impl Solver<'_> {
+    // Operators between the numbers, evaluated left to right, that give the result
+    fn witness(&self, result: &Integer, numbers: &[Integer]) -> Result<Option<Vec<Operator>>> {
+        if numbers.is_empty() {
+            return Ok(None);
+        }
+        self.find(result, numbers, self.can_prune(numbers))
+    }
+
+    // Number of distinct operator assignments that give the result
+    fn count(&self, result: &Integer, numbers: &[Integer]) -> Result<u64> {
+        if numbers.is_empty() {
+            return Ok(0);
+        }
+        self.count_from(result, numbers, self.can_prune(numbers))
     }
 
-    if result <= 0 {
-        return false;
+    fn can_prune(&self, numbers: &[Integer]) -> bool {
+        self.operators.iter().all(|op| op.is_growing())
+            && numbers.iter().all(|n| !n.is_negative() && !n.is_zero())
     }
 
-    let last = numbers[len - 1];
-    if result % last == 0 {
-        if can_be_true_sum_mul_concatenation(result / last, numbers, len - 1) {
-            return true;
+    // Searches backwards from the last number, undoing one operator at a time
+    fn find(&self, result: &Integer, numbers: &[Integer], prune: bool) -> Result<Option<Vec<Operator>>> {
+        let (last, rest) = numbers.split_last().unwrap();
+        if rest.is_empty() {
+            return Ok(if result == last { Some(Vec::new()) } else { None });
         }
+        if prune && (result.is_negative() || result.is_zero()) {
+            return Ok(None);
+        }
+
+        for &operator in self.operators.iter() {
+            let found = match operator.invert(result, last)? {
+                Inverse::Value(previous) => self.find(&previous, rest, prune)?,
+                Inverse::Any => self.evaluations(rest)?.into_iter().next(),
+                Inverse::Nothing => None,
+            };
+            if let Some(mut operators) = found {
+                operators.push(operator);
+                return Ok(Some(operators));
+            }
+        }
+
+        Ok(None)
     }
 
-    if can_be_true_sum_mul_concatenation(result - last, numbers, len - 1) {
-        return true;
+    fn count_from(&self, result: &Integer, numbers: &[Integer], prune: bool) -> Result<u64> {
+        let (last, rest) = numbers.split_last().unwrap();
+        if rest.is_empty() {
+            return Ok(if result == last { 1 } else { 0 });
+        }
+        if prune && (result.is_negative() || result.is_zero()) {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        for operator in self.operators.iter() {
+            count += match operator.invert(result, last)? {
+                Inverse::Value(previous) => self.count_from(&previous, rest, prune)?,
+                Inverse::Any => self.evaluations(rest)?.len() as u64,
+                Inverse::Nothing => 0,
+            };
+        }
+        Ok(count)
     }
 
-    if len < 2 {
-        return false;
+    // All operator assignments the numbers can be evaluated with
+    fn evaluations(&self, numbers: &[Integer]) -> Result<Vec<Vec<Operator>>> {
+        let mut complete = Vec::new();
+        let mut stack = vec![(numbers[0].clone(), 1, Vec::new())];
+        while let Some((value, next, operators)) = stack.pop() {
+            if next == numbers.len() {
+                complete.push(operators);
+                continue;
+            }
+            for &operator in self.operators.iter() {
+                let Some(value) = operator.apply(&value, &numbers[next])? else { continue };
+                let mut operators = operators.clone();
+                operators.push(operator);
+                stack.push((value, next + 1, operators));
+            }
+        }
+        Ok(complete)
     }
+}
 
-    let result_str = result.to_string();
-    let last_str = last.to_string();
-    if let Some(prefix) = result_str.strip_suffix(&last_str) {
-        if !prefix.is_empty() {
-            can_be_true_sum_mul_concatenation(prefix.parse::<i64>().unwrap(), numbers, len - 1)
-        } else {
-            false
+fn format_expression(numbers: &[Integer], operators: &[Operator]) -> String {
+    let mut expression = numbers[0].to_string();
+    for (operator, number) in operators.iter().zip(numbers[1..].iter()) {
+        expression.push_str(&format!(" {} {}", operator.symbol(), number));
+    }
+    expression
+}
+
+#[derive(Debug, PartialEq)]
+struct Audit {
+    result: Integer,
+    numbers: Vec<Integer>,
+    expression: Option<String>,
+    assignments: u64,
+}
+
+impl Display for Audit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.expression {
+            Some(expression) => write!(f, "{} = {} ({} assignments)", self.result, expression, self.assignments),
+            None => write!(f, "{} can't be made true", self.result),
         }
-    } else {
-        false
-    }
-}
-
-fn read_input<R: BufRead>(reader: R) -> Result<Vec<(i64, Vec<i64>)>> {
-    let equations: Vec<(i64, Vec<i64>)> = reader
-        .lines()
-        .flatten()
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let result_rest: Vec<&str> = line.split(": ").collect();
-            let result = result_rest[0].parse::<i64>().expect("Can't parse result");
-            let rest = result_rest[1]
-                .split_whitespace()
-                .map(|s| s.parse::<i64>().expect("Can't parse number"))
-                .collect();
-            (result, rest)
-        })
-        .collect();
+    }
+}
+
+fn audit<R: BufRead>(reader: R, operators: &[Operator]) -> Result<Vec<Audit>> {
+    let solver = Solver { operators };
+    read_input(reader)?
+        .into_iter()
+        .map(|(result, numbers)| Ok(Audit {
+            expression: solver.witness(&result, &numbers)?.map(|ops| format_expression(&numbers, &ops)),
+            assignments: solver.count(&result, &numbers)?,
+            result,
+            numbers,
+        }))
+        .collect()
+}
+
+fn read_input<R: BufRead>(reader: R) -> Result<Vec<(Integer, Vec<Integer>)>> {
+    let mut equations = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let (result, rest) = line.split_once(": ")
+            .ok_or_else(|| anyhow!("Can't parse equation \"{}\"", line))?;
+        let numbers = rest
+            .split_whitespace()
+            .map(|s| s.parse::<Integer>())
+            .collect::<Result<Vec<Integer>>>()?;
+        equations.push((result.parse::<Integer>()?, numbers));
+    }
     Ok(equations)
 }
 
+fn total_calibration_result(equations: &[(Integer, Vec<Integer>)], solver: &Solver) -> Result<i64> {
+    let mut valid = Vec::new();
+    for (result, numbers) in equations.iter() {
+        if solver.witness(result, numbers)?.is_some() {
+            valid.push(result);
+        }
+    }
+    Integer::sum(valid)?.to_i64()
+}
+
 //noinspection DuplicatedCode
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let equations: Vec<(i64, Vec<i64>)> = read_input(reader)?;
-
-    Ok(equations
-        .iter()
-        .filter(|(result, numbers)| can_be_true_sum_mul(result.clone(), numbers, numbers.len()))
-        .map(|(result, _)| result)
-        .sum())
+    let equations = read_input(reader)?;
+    let solver = Solver { operators: &[Operator::Add, Operator::Multiply] };
+    total_calibration_result(&equations, &solver)
 }
 
 //noinspection DuplicatedCode
 fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let equations: Vec<(i64, Vec<i64>)> = read_input(reader)?;
-    Ok(equations
-        .iter()
-        .filter(|(result, numbers)| can_be_true_sum_mul_concatenation(result.clone(), numbers, numbers.len()))
-        .map(|(result, _)| result)
-        .sum())
+    let equations = read_input(reader)?;
+    let solver = Solver { operators: &[Operator::Add, Operator::Multiply, Operator::Concatenate] };
+    total_calibration_result(&equations, &solver)
+}
+
+// How many part 2 equations hold and the one that holds in the most ways
+fn part2_audit<R: BufRead>(reader: R) -> Result<String> {
+    let audits = audit(reader, &[Operator::Add, Operator::Multiply, Operator::Concatenate])?;
+    let valid = audits.iter().filter(|audit| audit.expression.is_some()).count();
+    let summary = format!("{} of {} equations hold", valid, audits.len());
+    Ok(match audits.iter().filter(|audit| audit.assignments > 0).max_by_key(|audit| audit.assignments) {
+        Some(most) => format!("{}, the most ways: {}", summary, most),
+        None => summary,
+    })
 }
 
 //#region
@@ -112,6 +264,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), part2_audit)?;
     Ok(())
 }
 
@@ -122,6 +275,149 @@ fn main() {
 
 //#endregion
 
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::BufReader;
+
+    fn integers(numbers: &[i64]) -> Vec<Integer> {
+        numbers.iter().map(|&n| Integer::from(n)).collect()
+    }
+
+    fn witness(result: i64, numbers: &[i64], operators: &[Operator]) -> Option<String> {
+        let numbers = integers(numbers);
+        Solver { operators }.witness(&Integer::from(result), &numbers).unwrap()
+            .map(|ops| format_expression(&numbers, &ops))
+    }
+
+    fn count(result: i64, numbers: &[i64], operators: &[Operator]) -> u64 {
+        Solver { operators }.count(&Integer::from(result), &integers(numbers)).unwrap()
+    }
+
+    #[test]
+    fn test_witness() {
+        let operators = [Operator::Add, Operator::Multiply, Operator::Concatenate];
+        assert_eq!(Some("10 * 19".to_string()), witness(190, &[10, 19], &operators));
+        assert_eq!(Some("15 || 6".to_string()), witness(156, &[15, 6], &operators));
+        assert_eq!(Some("6 * 8 || 6 * 15".to_string()), witness(7290, &[6, 8, 6, 15], &operators));
+        assert_eq!(None, witness(83, &[17, 5], &operators));
+    }
+
+    #[test]
+    fn test_custom_operators() {
+        assert_eq!(Some("10 - 3".to_string()), witness(7, &[10, 3], &[Operator::Subtract]));
+        assert_eq!(Some("5 ^ 3".to_string()), witness(6, &[5, 3], &[Operator::Add, Operator::Xor]));
+        assert_eq!(Some("2 - 5 * 3".to_string()), witness(-9, &[2, 5, 3], &[Operator::Subtract, Operator::Multiply]));
+        assert_eq!(Some("4 * 7 * 0".to_string()), witness(0, &[4, 7, 0], &[Operator::Add, Operator::Multiply]));
+        assert_eq!(Some("-1 || 23".to_string()), witness(-123, &[-1, 23], &[Operator::Concatenate]));
+    }
+
+    #[test]
+    fn test_count() {
+        let operators = [Operator::Add, Operator::Multiply];
+        assert_eq!(2, count(3267, &[81, 40, 27], &operators));
+        assert_eq!(1, count(292, &[11, 6, 16, 20], &operators));
+        assert_eq!(0, count(83, &[17, 5], &operators));
+        assert_eq!(2, count(0, &[3, 4, 0], &operators));
+    }
+
+    #[test]
+    fn test_audit() {
+        let input = indoc! {"
+            3267: 81 40 27
+            83: 17 5
+        "};
+
+        let audits = audit(BufReader::new(input.as_bytes()), &[Operator::Add, Operator::Multiply]).unwrap();
+
+        assert_eq!(audits, vec![
+            Audit {
+                result: Integer::from(3267),
+                numbers: integers(&[81, 40, 27]),
+                expression: Some("81 * 40 + 27".to_string()),
+                assignments: 2,
+            },
+            Audit { result: Integer::from(83), numbers: integers(&[17, 5]), expression: None, assignments: 0 },
+        ]);
+    }
+
+    #[test]
+    fn test_part2_audit() {
+        let input = indoc! {"
+            190: 10 19
+            3267: 81 40 27
+            83: 17 5
+            156: 15 6
+            7290: 6 8 6 15
+            161011: 16 10 13
+            192: 17 8 14
+            21037: 9 7 18 13
+            292: 11 6 16 20
+        "};
+        assert_eq!(
+            "6 of 9 equations hold, the most ways: 3267 = 81 * 40 + 27 (2 assignments)",
+            part2_audit(BufReader::new(input.as_bytes())).unwrap()
+        );
+        assert_eq!("83 can't be made true", audit(BufReader::new("83: 17 5".as_bytes()), &[Operator::Add]).unwrap()[0].to_string());
+    }
+
+    #[test]
+    fn test_negative_concatenation() {
+        assert_eq!(None, witness(-5, &[0, 5], &[Operator::Concatenate]));
+        assert_eq!(0, count(-5, &[0, 5], &[Operator::Concatenate]));
+        assert_eq!(Some("-1 || 5".to_string()), witness(-15, &[-1, 5], &[Operator::Concatenate]));
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_overflowing_branches() {
+        let error = |result: i64, numbers: &[i64], operators: &[Operator]| {
+            let solver = Solver { operators };
+            let (result, numbers) = (Integer::from(result), integers(numbers));
+            assert!(solver.witness(&result, &numbers).is_err());
+            solver.count(&result, &numbers).unwrap_err().to_string()
+        };
+        assert_eq!(
+            "Integer overflow in 9223372036854775807 + 2",
+            error(0, &[i64::MAX, 2, 0], &[Operator::Add, Operator::Multiply])
+        );
+        assert_eq!(
+            "Integer overflow in 1000000000000000000 * 10",
+            error(1_000_000_000_000_000_000, &[0, 1_000_000_000_000_000_000], &[Operator::Concatenate, Operator::Add])
+        );
+        assert_eq!(2, count(0, &[i64::MAX, 1, 0], &[Operator::Subtract, Operator::Multiply]));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_overflowing_branches() {
+        let operators = [Operator::Add, Operator::Multiply];
+        assert_eq!(2, count(0, &[i64::MAX, 2, 0], &operators));
+        assert!(witness(0, &[i64::MAX, 2, 0], &operators).is_some());
+
+        let big = 1_000_000_000_000_000_000;
+        assert_eq!(Some(format!("0 || {}", big)), witness(big, &[0, big], &[Operator::Concatenate, Operator::Add]));
+        assert_eq!(2, count(big, &[0, big], &[Operator::Concatenate, Operator::Add]));
+        assert_eq!(2, count(0, &[i64::MAX, 1, 0], &[Operator::Subtract, Operator::Multiply]));
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_oversized() {
+        let input = "92233720368547758070: 9223372036854775807 10\n";
+        assert!(part2(BufReader::new(input.as_bytes())).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_oversized() {
+        let input = "92233720368547758070: 9223372036854775807 10\n1: 1\n";
+        let audits = audit(BufReader::new(input.as_bytes()), &[Operator::Multiply]).unwrap();
+        assert_eq!(Some("9223372036854775807 * 10".to_string()), audits[0].expression);
+    }
+}
+
 //noinspection SpellCheckingInspection
 #[cfg(test)]
 mod part1_tests {
@@ -1,140 +1,156 @@
-use std::cmp::min;
+use std::cmp::{min, Reverse};
+use std::collections::BinaryHeap;
 use advent_of_code2024_rust::{day, run_on_day_input};
+use advent_of_code2024_rust::numeric::Integer;
 use anyhow::*;
+use std::fmt::{Display, Formatter};
 use std::io::{BufRead};
 
-fn parse_input<R: BufRead>(reader: R) -> Vec<usize> {
-    let str: String = reader.lines().next().unwrap().unwrap();
-    
-    str.chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect()
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+struct Span {
+    id: usize,
+    start: usize,
+    size: usize,
+}
+
+// Files as spans of blocks, a file split by block compaction has several spans with the same id
+#[derive(Clone, Debug, PartialEq)]
+struct DiskMap {
+    files: Vec<Span>,
+    gaps: Vec<Span>,
+    len: usize,
 }
 
-fn check_sum(blocks: &Vec<Block>) -> i64 {
-    let mut hash: i64 = 0;
-    let mut index: i64 = 0;
-    for block in blocks {
-        if block.is_file {
-            for _j in 0..block.size {
-                hash = hash.checked_add(index * block.id).unwrap();
-                index += 1;
+impl DiskMap {
+    // Dense format from the puzzle: alternating file and free space sizes
+    fn parse(line: &str) -> Result<DiskMap> {
+        let mut files = Vec::new();
+        let mut gaps = Vec::new();
+        let mut start = 0;
+        for (i, c) in line.trim().chars().enumerate() {
+            let size = c.to_digit(10)
+                .ok_or_else(|| anyhow!("Invalid size '{}' at position {}", c, i))? as usize;
+            if i % 2 == 0 {
+                files.push(Span { id: i / 2, start, size });
+            } else if size > 0 {
+                gaps.push(Span { id: 0, start, size });
             }
-        } else {
-            index += block.size;
+            start += size;
         }
+        Ok(DiskMap { files, gaps, len: start })
     }
-    hash
-}
 
-//noinspection DuplicatedCode
-fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let blocks = parse_input(reader);
-    if blocks.is_empty() {
-        return Ok(0);
+    fn check_sum(&self) -> Result<i64> {
+        let mut hash = Integer::zero();
+        for file in self.files.iter() {
+            // id * (start + (start + 1) + ... + (start + size - 1))
+            let size = file.size as i64;
+            let positions = Integer::from(size).mul(&Integer::from(file.start as i64))?
+                .add(&Integer::from(size * (size - 1) / 2))?;
+            hash = hash.add(&positions.mul(&Integer::from(file.id as i64))?)?;
+        }
+        hash.to_i64()
     }
 
-    // true - file, false - free spase
-    let mut map: Vec<(usize, usize, bool)> =
-        blocks.iter().enumerate().map(|(i, &b)| (i / 2, b, i % 2 == 0)).collect();
+    // Moves single blocks from the end of the disk into the leftmost free block
+    fn compact_blocks(&self) -> DiskMap {
+        let mut remaining: Vec<Span> = self.files.clone();
+        remaining.sort_by_key(|file| file.start);
+        let mut gaps: Vec<Span> = self.gaps.clone();
+        gaps.sort_by_key(|gap| gap.start);
+
+        let mut moved: Vec<Span> = Vec::new();
+        for mut gap in gaps {
+            while gap.size > 0 {
+                let Some(last) = remaining.last_mut() else { break };
+                if last.start < gap.start {
+                    break;
+                }
+                let size = min(gap.size, last.size);
+                moved.push(Span { id: last.id, start: gap.start, size });
+                gap.start += size;
+                gap.size -= size;
+                last.size -= size;
+                if last.size == 0 {
+                    remaining.pop();
+                }
+            }
+        }
 
-    let mut last_file_index = if (map.len() % 2) == 0 { map.len() - 2 } else { map.len() - 1 };
-    let mut next = 0;
-    let mut compact_blocks: Vec<(i64, i64, bool)> = Vec::new();
+        let mut files = remaining;
+        files.extend(moved);
+        files.sort_by_key(|file| file.start);
+        let gaps = DiskMap::free_spans(&files, self.len);
+        DiskMap { files, gaps, len: self.len }
+    }
 
-    while next <= last_file_index {
-        let (id, size, is_file) = map[next];
-        if is_file {
-            compact_blocks.push((id as i64, size as i64, true));
-            next += 1;
-            continue;
+    // Moves whole files, highest id first, into the leftmost gap that fits them
+    fn compact_files(&self) -> DiskMap {
+        let max_gap = self.gaps.iter().map(|gap| gap.size).max().unwrap_or(0);
+        // Starts of free gaps bucketed by gap size
+        let mut free: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); max_gap + 1];
+        for gap in self.gaps.iter() {
+            free[gap.size].push(Reverse(gap.start));
         }
 
-        let (last_id, last_size, last_is_file) = map[last_file_index];
-        assert!(last_is_file,
-                "next(index: {}, id: {}, size: {}), last(index: {}, id: {}, size:{})",
-                next, id, size, last_file_index, last_id, last_size
-        );
-
-        let fill_size = min(size, last_size);
-        if fill_size != 0 {
-            compact_blocks.push((last_id as i64, fill_size as i64, true));
+        let mut files = self.files.clone();
+        files.sort_by_key(|file| Reverse(file.id));
+        for file in files.iter_mut() {
+            let best = (file.size..free.len())
+                .filter_map(|size| free[size].peek().map(|&Reverse(start)| (start, size)))
+                .filter(|&(start, _)| start < file.start)
+                .min();
+            if let Some((start, size)) = best {
+                free[size].pop();
+                file.start = start;
+                if size > file.size {
+                    free[size - file.size].push(Reverse(start + file.size));
+                }
+            }
         }
 
-        if fill_size == size {
-            next += 1;
-        } else {
-            map[next] = (id, size - fill_size, false)
+        files.sort_by_key(|file| file.start);
+        let gaps = DiskMap::free_spans(&files, self.len);
+        DiskMap { files, gaps, len: self.len }
+    }
+
+    fn free_spans(files: &[Span], len: usize) -> Vec<Span> {
+        let mut gaps = Vec::new();
+        let mut position = 0;
+        for file in files.iter().chain([Span { id: 0, start: len, size: 0 }].iter()) {
+            if file.start > position {
+                gaps.push(Span { id: 0, start: position, size: file.start - position });
+            }
+            position = position.max(file.start + file.size);
         }
+        gaps
+    }
+}
 
-        if fill_size == last_size {
-            last_file_index -= 2;
-        } else {
-            map[last_file_index] = (last_id, last_size - fill_size, true)
+// Block layout like "00...111", ids are written by their last digit
+impl Display for DiskMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut layout = vec!['.'; self.len];
+        for file in self.files.iter() {
+            let digit = char::from_digit((file.id % 10) as u32, 10).unwrap();
+            layout[file.start..file.start + file.size].fill(digit);
         }
+        write!(f, "{}", layout.into_iter().collect::<String>())
     }
+}
 
-    Ok(check_sum(compact_blocks.iter()
-        .copied()
-        .map(|(id, size, is_file) | {Block { id, size, is_file }})
-        .collect::<Vec<Block>>()
-        .as_ref()
-    ))
+fn parse_input<R: BufRead>(reader: R) -> Result<DiskMap> {
+    let line = reader.lines().next().transpose()?.unwrap_or_default();
+    DiskMap::parse(&line)
 }
 
-#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
-struct Block {
-    id: i64,
-    size: i64,
-    is_file: bool,
+//noinspection DuplicatedCode
+fn part1<R: BufRead>(reader: R) -> Result<i64> {
+    parse_input(reader)?.compact_blocks().check_sum()
 }
 
 fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let blocks: Vec<Block> = parse_input(reader).iter().enumerate()
-        .map(|(i, &b)| {
-            Block { id: (i / 2) as i64, size: b as i64, is_file: (i % 2) == 0 }
-        })
-        .collect();
-
-    if blocks.is_empty() {
-        return Ok(0);
-    }
-
-    let mut movable_blocks: Vec<Vec<Block>> = blocks.iter().map(|block| vec![block.clone()]).collect();
-    for index in (4..movable_blocks.len()).rev() {
-        let file_block = movable_blocks[index].first().unwrap().clone();
-        if !file_block.is_file {
-            continue
-        }
-
-        for free_space_index in 1..index {
-            let free_space_block = movable_blocks[free_space_index].first().unwrap().clone();
-            if free_space_block.is_file || free_space_block.size < file_block.size {
-                continue
-            }
-
-            assert_eq!(movable_blocks[free_space_index].len(), 1);
-
-            (&mut movable_blocks[free_space_index])[0] = Block {
-                id: -free_space_block.id,
-                size: free_space_block.size - file_block.size,
-                is_file: false
-            };
-
-            movable_blocks[free_space_index - 1].push(file_block.clone());
-            (&mut movable_blocks[index])[0] = Block {
-                id: -file_block.id,
-                size: file_block.size,
-                is_file: false
-            };
-
-            break;
-        }
-    }
-
-    let final_blocks: Vec<Block> = movable_blocks.iter().flatten().copied().collect();
-
-    Ok(check_sum(final_blocks.as_ref()))
+    parse_input(reader)?.compact_files().check_sum()
 }
 
 //#region
@@ -162,64 +178,138 @@ mod tests {
     use indoc::indoc;
     use super::*;
 
+    // Reads back the block layout written by Display
+    fn from_layout(layout: &str) -> Result<DiskMap> {
+        let mut files: Vec<Span> = Vec::new();
+        let mut gaps: Vec<Span> = Vec::new();
+        for (position, c) in layout.chars().enumerate() {
+            let (spans, id) = match c {
+                '.' => (&mut gaps, 0),
+                _ => (&mut files, c.to_digit(10).ok_or_else(|| anyhow!("Invalid block '{}'", c))? as usize),
+            };
+            match spans.last_mut() {
+                Some(last) if last.id == id && last.start + last.size == position => last.size += 1,
+                _ => spans.push(Span { id, start: position, size: 1 }),
+            }
+        }
+        Ok(DiskMap { files, gaps, len: layout.len() })
+    }
+
     #[cfg(test)]
     mod utils_tests {
         use super::*;
 
-        fn assert_checksum(expect: i64, blocks: &Vec<(i64, i64, bool)>) {
-            assert_eq!(expect,
-                check_sum(
-                    blocks.iter()
-                        .copied()
-                        .map(|(id, size, is_file)| Block { id, size, is_file })
-                        .collect::<Vec<Block>>()
-                        .as_ref()
-                )
-            )
+        fn assert_checksum(expect: i64, layout: &str) {
+            assert_eq!(expect, from_layout(layout).unwrap().check_sum().unwrap())
         }
 
         #[test]
         fn test1() {
-            assert_checksum(2858, [
-                // 00992111777.44.333....5555.6666.....8888..
-                (0, 2, true),
-                (9, 2, true),
-                (2, 1, true),
-                (1, 3, true),
-                (7, 3, true),
-                (0, 1, false),
-                (4, 2, true),
-                (0, 1, false),
-                (3, 3, true),
-                (0, 4, false),
-                (5, 4, true),
-                (0, 1, false),
-                (6, 4, true),
-                (0, 5, false),
-                (8, 4, true),
-                (0, 2, false)
-            ].to_vec().as_ref());
+            assert_checksum(2858, "00992111777.44.333....5555.6666.....8888..");
         }
 
         #[test]
         fn test2() {
-            assert_checksum(1928, [
-                // 0099811188827773336446555566..............
-                (0, 2, true),
-                (9, 2, true),
-                (8, 1, true),
-                (1, 3, true),
-                (8, 3, true),
-                (2, 1, true),
-                (7, 3, true),
-                (3, 3, true),
-                (6, 1, true),
-                (4, 2, true),
-                (6, 1, true),
-                (5, 4, true),
-                (6, 2, true),
-                (50, 14, false)
-            ].to_vec().as_ref());
+            assert_checksum(1928, "0099811188827773336446555566..............");
+        }
+
+        #[test]
+        fn test_check_sum_overflow() {
+            let disk_map = DiskMap {
+                files: vec![Span { id: usize::MAX / 4, start: 8, size: 1 }],
+                gaps: vec![Span { id: 0, start: 0, size: 8 }],
+                len: 9,
+            };
+            assert!(disk_map.check_sum().is_err());
+        }
+    }
+
+    #[cfg(test)]
+    mod disk_map_tests {
+        use super::*;
+
+        #[test]
+        fn test_display() {
+            let disk_map = DiskMap::parse("2333133121414131402").unwrap();
+            assert_eq!("00...111...2...333.44.5555.6666.777.888899", disk_map.to_string());
+            assert_eq!(disk_map, from_layout(&disk_map.to_string()).unwrap());
+        }
+
+        #[test]
+        fn test_compact_blocks() {
+            let disk_map = DiskMap::parse("12345").unwrap();
+            assert_eq!("022111222......", disk_map.compact_blocks().to_string());
+            assert_eq!(
+                "0099811188827773336446555566..............",
+                DiskMap::parse("2333133121414131402").unwrap().compact_blocks().to_string()
+            );
+        }
+
+        #[test]
+        fn test_compact_files() {
+            let compacted = DiskMap::parse("2333133121414131402").unwrap().compact_files();
+            assert_eq!("00992111777.44.333....5555.6666.....8888..", compacted.to_string());
+            assert_eq!(
+                vec![(11, 1), (14, 1), (18, 4), (26, 1), (31, 5), (40, 2)],
+                compacted.gaps.iter().map(|gap| (gap.start, gap.size)).collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn test_parse_error() {
+            assert_eq!(
+                "Invalid size 'x' at position 2",
+                DiskMap::parse("12x").unwrap_err().to_string()
+            );
+        }
+
+        // Block by block version of compact_files: each file goes to the first run of free blocks
+        // left of it that is long enough
+        fn reference_compact_files_checksum(line: &str) -> i64 {
+            let mut blocks: Vec<Option<usize>> = Vec::new();
+            let mut files: Vec<(usize, usize)> = Vec::new();
+            for (i, c) in line.chars().enumerate() {
+                let size = c.to_digit(10).unwrap() as usize;
+                if i % 2 == 0 {
+                    files.push((blocks.len(), size));
+                }
+                let id = if i % 2 == 0 { Some(i / 2) } else { None };
+                blocks.extend(std::iter::repeat_n(id, size));
+            }
+
+            let mut first_free = 0;
+            for (id, &(start, size)) in files.iter().enumerate().rev() {
+                while first_free < blocks.len() && blocks[first_free].is_some() {
+                    first_free += 1;
+                }
+                let mut run = 0;
+                for position in first_free..start {
+                    run = if blocks[position].is_none() { run + 1 } else { 0 };
+                    if run == size {
+                        blocks[position + 1 - size..=position].fill(Some(id));
+                        blocks[start..start + size].fill(None);
+                        break;
+                    }
+                }
+            }
+
+            blocks.iter().enumerate()
+                .filter_map(|(position, block)| block.map(|id| (position * id) as i64))
+                .sum()
+        }
+
+        #[test]
+        fn test_large_disk() {
+            assert_eq!(2858, reference_compact_files_checksum("2333133121414131402"));
+            let line: String = (0..20000).map(|i| char::from_digit((i * 7 % 9 + 1) as u32, 10).unwrap()).collect();
+            let disk_map = DiskMap::parse(&line).unwrap();
+            let compacted = disk_map.compact_files();
+            assert_eq!(disk_map.files.len(), compacted.files.len());
+            assert_eq!(
+                disk_map.files.iter().map(|f| f.size).sum::<usize>(),
+                compacted.files.iter().map(|f| f.size).sum::<usize>()
+            );
+            assert_eq!(reference_compact_files_checksum(&line), compacted.check_sum().unwrap());
         }
     }
 
@@ -227,6 +317,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -244,7 +335,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(6241633730082, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -252,6 +343,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: i64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -269,8 +361,9 @@ mod tests {
 
         #[test]
         fn test2() {
+            // 0.1 -> 01.
             test_part2(
-                2,
+                1,
                 indoc! {"
                     111
                 "},
@@ -279,14 +372,12 @@ mod tests {
 
         #[test]
         fn test3() {
-            test_part2(16, "11122"); // 1.122
+            test_part2(15, "11122"); // 0.1..22 -> 0.122.. -> 01.22..
         }
 
         #[test]
         fn part2_final() {
-            // Too high?
-            let result = run_on_day_input(day!(), part2).unwrap();
-            assert_eq!(6265268809555, result);
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
@@ -153,6 +153,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -165,7 +166,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(193607, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -173,10 +174,11 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         #[test]
         fn part2_final() {
-            assert_eq!(229557103025807, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
    }
 }
@@ -209,6 +209,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: i64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -291,9 +292,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            fn part2_final() {
-                assert_eq!(911750, run_on_day_input(day!(), part2).unwrap());
-            }
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
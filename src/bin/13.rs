@@ -1,128 +1,128 @@
 use advent_of_code2024_rust::{day, run_on_day_input};
+use advent_of_code2024_rust::linear::min_cost_combination_2d;
+use advent_of_code2024_rust::numeric::Integer;
 use anyhow::*;
 use std::io::{BufRead};
 use regex::Regex;
 
 #[derive(Debug, Clone)]
 struct ClawMachine {
-    a_x: i64,
-    a_y: i64,
-    b_x: i64,
-    b_y: i64,
-    prize_x: i64,
-    prize_y: i64,
+    // Moves of buttons A, B, C, ... in order
+    buttons: Vec<(i64, i64)>,
+    prize: (i64, i64),
 }
 
-fn parse_input<R: BufRead>(reader: R) -> Vec<ClawMachine> {
-    let button_regex = Regex::new(r"X\+(\d+), Y\+(\d+)").unwrap();
-    let prize_regex = Regex::new(r"X=(\d+), Y=(\d+)").unwrap();
-
-    reader
-        .lines()
-        .flatten()
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<String>>()
-        .chunks(3)
-        .map(|chunk| {
-            assert_eq!(chunk.len(), 3);
-
-            let a_caps = button_regex.captures(&chunk[0])
-                .ok_or_else(|| anyhow!("Invalid input for Button A"))?;
-            let b_caps = button_regex.captures(&chunk[1])
-                .ok_or_else(|| anyhow!("Invalid input for Button B"))?;
-            let prize_caps = prize_regex.captures(&chunk[2])
-                .ok_or_else(|| anyhow!("Invalid input for Prize"))?;
-
-            let a_x = a_caps[1].parse::<i64>().unwrap();
-            let a_y = a_caps[2].parse::<i64>().unwrap();
-            let b_x = b_caps[1].parse::<i64>().unwrap();
-            let b_y = b_caps[2].parse::<i64>().unwrap();
-            let prize_x = prize_caps[1].parse::<i64>().unwrap();
-            let prize_y = prize_caps[2].parse::<i64>().unwrap();
-
-            Ok(ClawMachine {
-                a_x, a_y,
-                b_x, b_y,
-                prize_x, prize_y,
-            })
-        })
-        .collect::<Result<Vec<ClawMachine>>>()
-        .unwrap()
+#[derive(Debug, Clone)]
+struct ClawConfig {
+    // Token cost of each button, indexed like ClawMachine::buttons
+    button_costs: Vec<i64>,
+    // Added to both prize coordinates
+    prize_offset: i64,
 }
 
-fn min_cost_to_win(machine: &ClawMachine) -> Option<i64> {
-    let a_top = machine.prize_y * machine.b_x - machine.prize_x * machine.b_y;
-    let a_bottom = machine.a_y * machine.b_x - machine.a_x * machine.b_y;
-
-    if a_bottom == 0 {
-        let a: Option<i64> = if machine.prize_y % machine.a_y == 0 && machine.prize_x % machine.a_x == 0 &&
-            machine.prize_y / machine.a_y == machine.prize_x / machine.a_x {
-            Some(machine.prize_y / machine.a_y)
-        } else {
-            None
-        };
-        let b: Option<i64> = if machine.prize_y % machine.b_y == 0 && machine.prize_x % machine.b_x == 0 &&
-            machine.prize_y / machine.b_y == machine.prize_x / machine.b_x {
-            Some(machine.prize_y / machine.b_y)
-        } else {
-            None
-        };
+impl ClawConfig {
+    fn part1() -> ClawConfig {
+        ClawConfig { button_costs: vec![3, 1], prize_offset: 0 }
+    }
 
-        return [a.and_then(|x| Some(x * 3)), b].iter().filter_map(|&x| x).min();
+    fn part2() -> ClawConfig {
+        ClawConfig { prize_offset: 10000000000000, ..ClawConfig::part1() }
     }
+}
 
-    if a_top % a_bottom != 0 {
-        return None;
+// Buttons are lettered like in the puzzle, any past Z go by their number
+fn button_name(index: usize) -> String {
+    if index < 26 {
+        ((b'A' + index as u8) as char).to_string()
+    } else {
+        format!("#{}", index + 1)
     }
+}
 
-    let a = a_top / a_bottom;
-    if a < 0 {
-        return None;
+fn parse_input<R: BufRead>(reader: R) -> Result<Vec<ClawMachine>> {
+    let button_regex = Regex::new(r"^Button ([A-Z]): X\+(\d+), Y\+(\d+)$").unwrap();
+    let prize_regex = Regex::new(r"^Prize: X=(\d+), Y=(\d+)$").unwrap();
+
+    let mut machines = Vec::new();
+    let mut buttons = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        let line_number = index + 1;
+        if line.is_empty() {
+            if !buttons.is_empty() {
+                bail!("Line {}: machine has no prize", line_number);
+            }
+        } else if let Some(captures) = button_regex.captures(line) {
+            let expected = button_name(buttons.len());
+            if captures[1] != *expected {
+                bail!("Line {}: expected button {}, got {}", line_number, expected, &captures[1]);
+            }
+            buttons.push((captures[2].parse::<i64>()?, captures[3].parse::<i64>()?));
+        } else if let Some(captures) = prize_regex.captures(line) {
+            if buttons.is_empty() {
+                bail!("Line {}: prize without buttons", line_number);
+            }
+            machines.push(ClawMachine {
+                buttons: std::mem::take(&mut buttons),
+                prize: (captures[1].parse::<i64>()?, captures[2].parse::<i64>()?),
+            });
+        } else {
+            bail!("Line {}: invalid input \"{}\"", line_number, line);
+        }
+    }
+    if !buttons.is_empty() {
+        bail!("Last machine has no prize");
     }
 
-    let b_top = machine.prize_x - a * machine.a_x;
-    let b_bottom = machine.b_x;
+    Ok(machines)
+}
 
-    if (b_top % b_bottom) != 0 {
-        return None;
+fn min_cost_to_win(machine: &ClawMachine, config: &ClawConfig) -> Result<Option<i64>> {
+    if machine.buttons.len() > config.button_costs.len() {
+        bail!("No cost for button {}", button_name(config.button_costs.len()));
     }
-    let b = b_top / b_bottom;
-    if b < 0 {
-        return None;
+    let buttons: Vec<[Integer; 2]> = machine.buttons.iter()
+        .map(|&(x, y)| [Integer::from(x), Integer::from(y)])
+        .collect();
+    let costs: Vec<Integer> = config.button_costs[..buttons.len()].iter()
+        .map(|&cost| Integer::from(cost))
+        .collect();
+    let offset = Integer::from(config.prize_offset);
+    let prize = [
+        Integer::from(machine.prize.0).add(&offset)?,
+        Integer::from(machine.prize.1).add(&offset)?,
+    ];
+
+    let Some(presses) = min_cost_combination_2d(&buttons, &costs, &prize)? else {
+        return Ok(None);
+    };
+    let cost = presses.iter().zip(costs.iter())
+        .try_fold(Integer::zero(), |acc, (presses, cost)| acc.add(&presses.mul(cost)?))?;
+    Ok(Some(cost.to_i64()?))
+}
+
+fn total_cost(machines: &[ClawMachine], config: &ClawConfig) -> Result<i64> {
+    let mut costs = Vec::new();
+    for machine in machines.iter() {
+        if let Some(cost) = min_cost_to_win(machine, config)? {
+            costs.push(Integer::from(cost));
+        }
     }
+    Integer::sum(costs.iter())?.to_i64()
+}
 
-    Some(a * 3 + b)
+fn solve_ext<R: BufRead>(reader: R, config: &ClawConfig) -> Result<i64> {
+    let machines = parse_input(reader)?;
+    total_cost(&machines, config)
 }
 
-//noinspection DuplicatedCode
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let machines = parse_input(reader);
-
-    let result: i64 = machines
-        .iter()
-        .filter_map(|machine| min_cost_to_win(machine))
-        .sum();
-
-    Ok(result)
+    solve_ext(reader, &ClawConfig::part1())
 }
 
-//noinspection DuplicatedCode
 fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let machines = parse_input(reader);
-
-    let result: i64 = machines
-        .iter()
-        .filter_map(|machine| min_cost_to_win(&ClawMachine {
-            a_x: machine.a_x,
-            a_y: machine.a_y,
-            b_x: machine.b_x,
-            b_y: machine.b_y,
-            prize_x: machine.prize_x + 10000000000000,
-            prize_y: machine.prize_y + 10000000000000,
-        }))
-        .sum();
-
-    Ok(result)
+    solve_ext(reader, &ClawConfig::part2())
 }
 
 //#region
@@ -154,53 +154,136 @@ mod tests {
     mod min_cost_to_win_tests {
         use super::*;
 
+        fn machine(a: (i64, i64), b: (i64, i64), prize: (i64, i64)) -> ClawMachine {
+            ClawMachine { buttons: vec![a, b], prize }
+        }
+
         fn test_min_cost_to_win(expect: Option<i64>, machine: &ClawMachine) {
-            assert_eq!(expect, min_cost_to_win(machine));
+            assert_eq!(expect, min_cost_to_win(machine, &ClawConfig::part1()).unwrap());
         }
 
         #[test]
         fn test1() {
-            test_min_cost_to_win(Some(2), &ClawMachine {
-                a_x: 1, a_y: 1,
-                b_x: 2, b_y: 2,
-                prize_x: 4, prize_y: 4,
-            });
+            test_min_cost_to_win(Some(2), &machine((1, 1), (2, 2), (4, 4)));
         }
 
         #[test]
         fn test2() {
-            test_min_cost_to_win(Some(6), &ClawMachine {
-                a_x: 4, a_y: 4,
-                b_x: 1, b_y: 1,
-                prize_x: 8, prize_y: 8,
-            });
+            test_min_cost_to_win(Some(6), &machine((4, 4), (1, 1), (8, 8)));
         }
 
         #[test]
         fn test3() {
-            test_min_cost_to_win(None, &ClawMachine {
-                a_x: 2, a_y: 3,
-                b_x: 4, b_y: 6,
-                prize_x: 9, prize_y: 9,
-            });
+            test_min_cost_to_win(None, &machine((2, 3), (4, 6), (9, 9)));
         }
 
         #[test]
         fn test4() {
-            test_min_cost_to_win(Some(2), &ClawMachine {
-                a_x: 12, a_y: 12,
-                b_x: 4, b_y: 4,
-                prize_x: 8, prize_y: 8,
-            });
+            test_min_cost_to_win(Some(2), &machine((12, 12), (4, 4), (8, 8)));
+        }
+
+        #[cfg(not(feature = "bigint"))]
+        #[test]
+        fn test_overflow() {
+            assert!(min_cost_to_win(&machine((3, i64::MAX), (5, 7), (8, 8)), &ClawConfig::part1()).is_err());
+        }
+
+        #[test]
+        fn test_collinear_combination() {
+            // Neither button alone reaches the prize
+            test_min_cost_to_win(Some(4), &machine((3, 3), (5, 5), (8, 8)));
+            test_min_cost_to_win(Some(7), &machine((6, 12), (1, 2), (13, 26)));
+        }
+
+        #[test]
+        fn test_collinear_off_line() {
+            test_min_cost_to_win(None, &machine((3, 3), (5, 5), (8, 9)));
+        }
+
+        #[test]
+        fn test_vertical_buttons() {
+            test_min_cost_to_win(Some(7), &machine((0, 2), (0, 3), (0, 7)));
         }
 
         #[test]
         fn test5() {
-            test_min_cost_to_win(Some(1), &ClawMachine {
-                a_x: 8, a_y: 8,
-                b_x: 8, b_y: 8,
-                prize_x: 8, prize_y: 8,
-            });
+            test_min_cost_to_win(Some(1), &machine((8, 8), (8, 8), (8, 8)));
+        }
+    }
+
+    #[cfg(test)]
+    mod config_tests {
+        use super::*;
+
+        const THREE_BUTTONS: &str = indoc! {"
+            Button A: X+1, Y+0
+            Button B: X+0, Y+1
+            Button C: X+1, Y+1
+            Prize: X=3, Y=2
+
+            Button A: X+2, Y+2
+            Button B: X+3, Y+3
+            Prize: X=7, Y=7
+        "};
+
+        fn solve(input: &str, config: &ClawConfig) -> Result<i64> {
+            solve_ext(BufReader::new(input.as_bytes()), config)
+        }
+
+        #[test]
+        fn test_three_buttons() {
+            let config = ClawConfig { button_costs: vec![3, 3, 1], prize_offset: 0 };
+            // A + 2 * C for the first machine, 2 * A + B for the second
+            assert_eq!(14, solve(THREE_BUTTONS, &config).unwrap());
+        }
+
+        #[test]
+        fn test_costs_and_offset() {
+            let config = ClawConfig { button_costs: vec![1, 1, 1], prize_offset: 1 };
+            // (4, 3) as A + 3 * C, (8, 8) as A + 2 * B
+            assert_eq!(7, solve(THREE_BUTTONS, &config).unwrap());
+        }
+
+        #[test]
+        fn test_crlf() {
+            let config = ClawConfig { button_costs: vec![3, 3, 1], prize_offset: 0 };
+            assert_eq!(14, solve(&THREE_BUTTONS.replace('\n', "\r\n"), &config).unwrap());
+        }
+
+        #[test]
+        fn test_missing_cost() {
+            assert_eq!(
+                "No cost for button C",
+                solve(THREE_BUTTONS, &ClawConfig::part1()).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn test_many_buttons() {
+            let machine = ClawMachine { buttons: vec![(1, 1); 300], prize: (2, 2) };
+            let config = ClawConfig { button_costs: vec![1; 199], prize_offset: 0 };
+            assert_eq!(
+                "No cost for button #200",
+                min_cost_to_win(&machine, &config).unwrap_err().to_string()
+            );
+
+            let mut input: String = ('A'..='Z').map(|name| format!("Button {}: X+1, Y+1\n", name)).collect();
+            input.push_str("Button A: X+1, Y+1\nPrize: X=2, Y=2\n");
+            assert_eq!(
+                "Line 27: expected button #27, got A",
+                parse_input(BufReader::new(input.as_bytes())).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn test_parse_errors() {
+            let error = |input: &str| parse_input(BufReader::new(input.as_bytes())).unwrap_err().to_string();
+            assert_eq!(
+                "Line 2: expected button B, got C",
+                error("Button A: X+1, Y+2\nButton C: X+1, Y+2\nPrize: X=1, Y=1\n")
+            );
+            assert_eq!("Line 2: machine has no prize", error("Button A: X+1, Y+2\n\n"));
+            assert_eq!("Line 1: invalid input \"Prize X=1\"", error("Prize X=1\n"));
         }
     }
 
@@ -208,6 +291,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -239,7 +323,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(26005, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -247,10 +331,33 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
+
+        #[test]
+        fn test1() {
+            let input = indoc! {"
+                Button A: X+94, Y+34
+                Button B: X+22, Y+67
+                Prize: X=8400, Y=5400
+
+                Button A: X+26, Y+66
+                Button B: X+67, Y+21
+                Prize: X=12748, Y=12176
+
+                Button A: X+17, Y+86
+                Button B: X+84, Y+37
+                Prize: X=7870, Y=6450
+
+                Button A: X+69, Y+23
+                Button B: X+27, Y+71
+                Prize: X=18641, Y=10279
+            "};
+            assert_eq!(875318608908, part2(BufReader::new(input.as_bytes())).unwrap());
+        }
 
         #[test]
         fn part2_final() {
-            assert_eq!(105620095782547, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
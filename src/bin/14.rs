@@ -1,7 +1,9 @@
 use advent_of_code2024_rust::{day, run_on_day_input};
 use anyhow::*;
-use std::io::{BufRead};
+use std::io::{BufRead, Write};
+use itertools::Itertools;
 
+#[derive(Clone)]
 struct Robot {
     pos: (i32, i32),
     vel: (i32, i32),
@@ -34,107 +36,194 @@ fn parse_input<R: BufRead>(reader: R) -> Vec<Robot> {
 }
 
 
-fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    part1_ext(reader, 101, 103)
+struct RobotSimulation {
+    robots: Vec<Robot>,
+    width: i32,
+    height: i32,
+    time: i64,
 }
 
-//noinspection DuplicatedCode
-fn part1_ext<R: BufRead>(reader: R, width: i32, height: i32) -> Result<i64> {
-    let robots = parse_input(reader);
+impl RobotSimulation {
+    fn new(robots: Vec<Robot>, width: i32, height: i32) -> RobotSimulation {
+        RobotSimulation { robots, width, height, time: 0 }
+    }
 
-    let final_positions: Vec<(i32, i32)> = robots
-        .into_iter()
-        .map(|robot: Robot| {
-            (
-                (robot.pos.0 + 100 * robot.vel.0).rem_euclid(width),
-                (robot.pos.1 + 100 * robot.vel.1).rem_euclid(height),
-            )
-        })
-        .collect();
-
-    // Count robots in each quadrant
-    let mut quadrants = [0; 4];
-    let mid_x = width / 2;
-    let mid_y = height / 2;
-    for (x, y) in final_positions {
-        if x == mid_x || y == mid_y {
-            continue; // Skip robots on the middle grid lines
-        }
-        match (x < mid_x, y < mid_y) {
-            (true, true) => quadrants[0] += 1,  // Top-left
-            (false, true) => quadrants[1] += 1, // Top-right
-            (true, false) => quadrants[2] += 1, // Bottom-left
-            (false, false) => quadrants[3] += 1, // Bottom-right
+    fn advance(&mut self, steps: i64) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        for robot in self.robots.iter_mut() {
+            robot.pos = (
+                (robot.pos.0 as i64 + steps * robot.vel.0 as i64).rem_euclid(width) as i32,
+                (robot.pos.1 as i64 + steps * robot.vel.1 as i64).rem_euclid(height) as i32,
+            );
         }
+        self.time += steps;
     }
 
-    // Calculate safety factor
-    Ok(quadrants.iter().product())
+    // Robots in top-left, top-right, bottom-left and bottom-right quadrants, middle lines are skipped
+    fn quadrant_counts(&self) -> [i64; 4] {
+        let mut quadrants = [0; 4];
+        let mid_x = self.width / 2;
+        let mid_y = self.height / 2;
+        for &Robot { pos: (x, y), .. } in self.robots.iter() {
+            if x == mid_x || y == mid_y {
+                continue;
+            }
+            match (x < mid_x, y < mid_y) {
+                (true, true) => quadrants[0] += 1,
+                (false, true) => quadrants[1] += 1,
+                (true, false) => quadrants[2] += 1,
+                (false, false) => quadrants[3] += 1,
+            }
+        }
+        quadrants
+    }
+
+    fn spread(&self) -> (i64, i64) {
+        (
+            spread(self.robots.iter().map(|robot| robot.pos.0)),
+            spread(self.robots.iter().map(|robot| robot.pos.1)),
+        )
+    }
 }
 
-//noinspection DuplicatedCode
-fn part2<R: BufRead>(reader: R) -> Result<i64> {
-    let mut robots = parse_input(reader);
-
-    let width = 101;
-    let height = 103;
-
-    let mut target_ordering = 2;
-
-    for i in 1..10000 {
-        let mut map = vec![vec![false; 101]; 103];
-
-        robots = robots
-            .into_iter()
-            .map(|robot: Robot| Robot {
-                pos: (
-                    (robot.pos.0 + robot.vel.0).rem_euclid(width),
-                    (robot.pos.1 + robot.vel.1).rem_euclid(height)
-                ),
-                vel: robot.vel
-            })
-            .inspect(|robot| {
-                map[robot.pos.1 as usize][robot.pos.0 as usize] = true;
-            })
-            .collect();
-
-        let ordering = map.iter()
-            .map(|row| {
-                let mut row_ordering = 0;
-                let mut current = 0;
-                for is_robot in row {
-                    if *is_robot {
-                        current += 1;
-                        if current > row_ordering {
-                            row_ordering = current;
-                        }
-                    } else {
-                        current = 0;
-                    }
-                }
-                row_ordering
-            })
-            .max().unwrap();
-
-        if ordering > target_ordering {
-            target_ordering = ordering;
-            println!("------------------------");
-            println!("{}", i);
-            for row in map {
-                for is_robot in row {
-                    if is_robot {
-                        print!("#");
-                    } else {
-                        print!(".");
+impl RobotSimulation {
+    fn occupancy(&self) -> Vec<Vec<usize>> {
+        let mut map = vec![vec![0; self.width as usize]; self.height as usize];
+        for robot in self.robots.iter() {
+            map[robot.pos.1 as usize][robot.pos.0 as usize] += 1;
+        }
+        map
+    }
+
+    // Robot counts in cell_size x cell_size blocks, rows first
+    fn density(&self, cell_size: usize) -> Vec<Vec<usize>> {
+        let rows = (self.height as usize).div_ceil(cell_size);
+        let columns = (self.width as usize).div_ceil(cell_size);
+        let mut heatmap = vec![vec![0; columns]; rows];
+        for robot in self.robots.iter() {
+            heatmap[robot.pos.1 as usize / cell_size][robot.pos.0 as usize / cell_size] += 1;
+        }
+        heatmap
+    }
+
+    // Sizes of groups of orthogonally adjacent occupied tiles, largest first
+    fn clusters(&self) -> Vec<usize> {
+        let map = self.occupancy();
+        let mut visited = vec![vec![false; self.width as usize]; self.height as usize];
+        let mut sizes = Vec::new();
+
+        for start in self.robots.iter().map(|robot| (robot.pos.0 as usize, robot.pos.1 as usize)) {
+            if visited[start.1][start.0] {
+                continue;
+            }
+            visited[start.1][start.0] = true;
+
+            let mut size = 0;
+            let mut stack = vec![start];
+            while let Some((x, y)) = stack.pop() {
+                size += 1;
+                let neighbours = [
+                    (x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if ny < map.len() && nx < map[ny].len() && map[ny][nx] > 0 && !visited[ny][nx] {
+                        visited[ny][nx] = true;
+                        stack.push((nx, ny));
                     }
                 }
-                println!()
             }
-            println!()
+            sizes.push(size);
         }
+
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
     }
 
-    Ok(0)
+    // Current frame as a plain PBM image, occupied tiles are black
+    #[allow(dead_code)]
+    fn write_pbm<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "P1")?;
+        writeln!(writer, "# step {}", self.time)?;
+        writeln!(writer, "{} {}", self.width, self.height)?;
+        for row in self.occupancy() {
+            writeln!(writer, "{}", row.iter().map(|&count| if count > 0 { "1" } else { "0" }).join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn part1<R: BufRead>(reader: R) -> Result<i64> {
+    part1_ext(reader, 101, 103)
+}
+
+//noinspection DuplicatedCode
+fn part1_ext<R: BufRead>(reader: R, width: i32, height: i32) -> Result<i64> {
+    let mut simulation = RobotSimulation::new(parse_input(reader), width, height);
+    simulation.advance(100);
+
+    // Calculate safety factor
+    Ok(simulation.quadrant_counts().iter().product())
+}
+
+fn part2<R: BufRead>(reader: R) -> Result<i64> {
+    part2_ext(reader, 101, 103)
+}
+
+// Scaled variance of the coordinates: n * sum(v^2) - sum(v)^2
+fn spread(values: impl Iterator<Item = i32>) -> i64 {
+    let (n, sum, sum_squares) = values.fold((0i64, 0i64, 0i64), |(n, sum, sum_squares), v| {
+        (n + 1, sum + v as i64, sum_squares + (v as i64) * (v as i64))
+    });
+    n * sum_squares - sum * sum
+}
+
+// Smallest t with t = a (mod m) and t = b (mod n)
+fn chinese_remainder(a: i32, m: i32, b: i32, n: i32) -> Option<i64> {
+    (0..n as i64)
+        .map(|k| a as i64 + k * m as i64)
+        .find(|t| t.rem_euclid(n as i64) == b as i64)
+}
+
+//noinspection DuplicatedCode
+fn part2_ext<R: BufRead>(reader: R, width: i32, height: i32) -> Result<i64> {
+    picture_step(parse_input(reader), width, height)
+}
+
+fn picture_step(robots: Vec<Robot>, width: i32, height: i32) -> Result<i64> {
+    let mut simulation = RobotSimulation::new(robots, width, height);
+
+    // x and y coordinates repeat independently with periods width and height, the picture appears
+    // when both are the most clustered at once
+    let (mut best_x, mut best_y) = ((i64::MAX, 0), (i64::MAX, 0));
+    for step in 0..width.max(height) {
+        let (spread_x, spread_y) = simulation.spread();
+        if step < width && spread_x < best_x.0 {
+            best_x = (spread_x, step);
+        }
+        if step < height && spread_y < best_y.0 {
+            best_y = (spread_y, step);
+        }
+        simulation.advance(1);
+    }
+    let (step_x, step_y) = (best_x.1, best_y.1);
+
+    chinese_remainder(step_x, width, step_y, height)
+        .ok_or_else(|| anyhow!("No step matches x step {} and y step {}", step_x, step_y))
+}
+
+// How tightly the robots gather at the part 2 step, the picture shows as one large cluster
+fn part2_frame<R: BufRead>(reader: R) -> Result<String> {
+    let robots = parse_input(reader);
+    let step = picture_step(robots.clone(), 101, 103)?;
+    let mut simulation = RobotSimulation::new(robots, 101, 103);
+    simulation.advance(step);
+
+    let largest_cluster = simulation.clusters().first().copied().unwrap_or(0);
+    let densest_block = simulation.density(10).iter().flatten().max().copied().unwrap_or(0);
+    Ok(format!(
+        "step {}: largest cluster {} robots, densest 10x10 block {} robots",
+        step, largest_cluster, densest_block
+    ))
 }
 
 //#region
@@ -146,6 +235,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), part2_frame)?;
     Ok(())
 }
 
@@ -162,10 +252,69 @@ mod tests {
     use indoc::indoc;
     use super::*;
 
+    #[cfg(test)]
+    mod simulation_tests {
+        use super::*;
+
+        fn simulation(input: &str, width: i32, height: i32) -> RobotSimulation {
+            RobotSimulation::new(parse_input(BufReader::new(input.as_bytes())), width, height)
+        }
+
+        #[test]
+        fn test_advance() {
+            let mut single = simulation("p=2,4 v=2,-3\n", 11, 7);
+            single.advance(5);
+            assert_eq!((1, 3), single.robots[0].pos);
+
+            let mut stepwise = simulation("p=2,4 v=2,-3\n", 11, 7);
+            for _ in 0..5 {
+                stepwise.advance(1);
+            }
+            assert_eq!((1, 3), stepwise.robots[0].pos);
+            assert_eq!(5, stepwise.time);
+        }
+
+        #[test]
+        fn test_statistics() {
+            let simulation = simulation(indoc! {"
+                p=0,0 v=0,0
+                p=1,0 v=0,0
+                p=1,1 v=0,0
+                p=4,4 v=0,0
+                p=2,0 v=0,0
+            "}, 5, 5);
+
+            assert_eq!([3, 0, 0, 1], simulation.quadrant_counts());
+            assert_eq!(vec![vec![3, 1, 0], vec![0, 0, 0], vec![0, 0, 1]], simulation.density(2));
+            assert_eq!(vec![4, 1], simulation.clusters());
+        }
+
+        #[test]
+        fn test_write_pbm() {
+            let mut simulation = simulation("p=0,0 v=1,1\np=2,1 v=0,0\n", 3, 2);
+            simulation.advance(1);
+
+            let mut output = Vec::new();
+            simulation.write_pbm(&mut output).unwrap();
+
+            assert_eq!(
+                indoc! {"
+                    P1
+                    # step 1
+                    3 2
+                    0 0 0
+                    0 1 1
+                "},
+                String::from_utf8(output).unwrap()
+            );
+        }
+    }
+
     //noinspection SpellCheckingInspection
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, width: i32, height: i32, input: &str) {
             assert_eq!(expect, part1_ext(BufReader::new(input.as_bytes()), width, height).unwrap());
@@ -195,7 +344,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(231852216, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -204,6 +353,55 @@ mod tests {
     mod part2_tests {
         use super::*;
 
+        // Robots scattered by velocities from a simple LCG that gather into a square at `step`
+        fn gathering_robots(width: i32, height: i32, step: i32) -> String {
+            let mut seed: i64 = 12345;
+            let mut next = |modulo: i32| {
+                seed = (seed * 1103515245 + 12345) % (1 << 31);
+                (seed % modulo as i64) as i32
+            };
+
+            let mut input = String::new();
+            for i in 0..100 {
+                let target = (40 + i % 10, 50 + i / 10);
+                let vel = (next(201) - 100, next(201) - 100);
+                let pos = (
+                    (target.0 - step * vel.0).rem_euclid(width),
+                    (target.1 - step * vel.1).rem_euclid(height),
+                );
+                input.push_str(&format!("p={},{} v={},{}\n", pos.0, pos.1, vel.0, vel.1));
+            }
+            input
+        }
+
+        fn test_part2(expect: i64, width: i32, height: i32, input: &str) {
+            assert_eq!(expect, part2_ext(BufReader::new(input.as_bytes()), width, height).unwrap());
+        }
+
+        #[test]
+        fn test_gathering() {
+            test_part2(7093, 101, 103, &gathering_robots(101, 103, 7093));
+        }
+
+        #[test]
+        fn test_gathering_early() {
+            test_part2(42, 101, 103, &gathering_robots(101, 103, 42));
+        }
+
+        #[test]
+        fn test_part2_frame() {
+            assert_eq!(
+                "step 7093: largest cluster 100 robots, densest 10x10 block 100 robots",
+                part2_frame(BufReader::new(gathering_robots(101, 103, 7093).as_bytes())).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_chinese_remainder() {
+            assert_eq!(Some(8), chinese_remainder(2, 3, 3, 5));
+            assert_eq!(None, chinese_remainder(1, 4, 0, 6));
+        }
+
         #[test]
         fn part2_final() {
             part2_result().unwrap();
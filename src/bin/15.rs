@@ -2,291 +2,341 @@ use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 use advent_of_code2024_rust::{day, run_on_day_input};
 use anyhow::*;
-use std::io::{BufRead};
+use std::io::{BufRead, Write};
 use array2d::Array2D;
 use advent_of_code2024_rust::matrix::{Array2DExt, Coordinate, Direction};
 use Direction::{Down, Left, Right, Up};
 use Tile::{Empty, Robot, Wall, Box};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Tile {
     Wall,
     Empty,
-    Box,
+    // Index of the box covering the tile
+    Box(usize),
     Robot,
 }
 
+// Rectangular box given by its top-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WarehouseBox {
+    corner: Coordinate,
+    height: isize,
+    width: isize,
+}
+
+impl WarehouseBox {
+    fn cells(self) -> impl Iterator<Item = Coordinate> {
+        (0..self.height).flat_map(move |row| (0..self.width).map(move |column| self.corner + (row, column)))
+    }
+}
+
+fn direction_char(direction: &Direction) -> char {
+    match direction {
+        Up => '^',
+        Down => 'v',
+        Left => '<',
+        Right => '>',
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MoveRecord {
+    direction: Direction,
+    moved: bool,
+    // Top-left corners of the pushed boxes before the move
+    pushed: Vec<Coordinate>,
+}
+
+impl Display for MoveRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", direction_char(&self.direction))?;
+        match (self.moved, self.pushed.len()) {
+            (false, _) => write!(f, "blocked"),
+            (true, 0) => write!(f, "moved"),
+            (true, 1) => write!(f, "moved, pushed 1 box"),
+            (true, n) => write!(f, "moved, pushed {} boxes", n),
+        }
+    }
+}
+
+// A warehouse that can replay moves one by one and take them back
+trait Replayable: Display {
+    fn step(&mut self, direction: &Direction) -> MoveRecord;
+    fn undo(&mut self, record: &MoveRecord);
+}
+
 #[derive(Debug, Clone)]
 struct Warehouse {
     map: Array2D<Tile>,
-    robot_pos: (usize, usize),
+    boxes: Vec<WarehouseBox>,
+    robot: Coordinate,
 }
 
-//noinspection DuplicatedCode
 impl Warehouse {
-    fn try_move(&mut self, direction: &Direction) -> bool {
-        let d = direction.to_offset();
+    // Every tile becomes a `height` x `width` block, boxes grow to fill their block and the robot
+    // keeps to the block's top-left corner
+    fn scaled(&self, height: usize, width: usize) -> Warehouse {
+        let mut map = Array2D::filled_with(Empty, self.map.num_rows() * height, self.map.num_columns() * width);
+        for y in 0..self.map.num_rows() {
+            for x in 0..self.map.num_columns() {
+                if self.map.get(y, x) == Some(&Wall) {
+                    for row in y * height..(y + 1) * height {
+                        for column in x * width..(x + 1) * width {
+                            map.set(row, column, Wall).unwrap();
+                        }
+                    }
+                }
+            }
+        }
 
-        let robot_coordinate = Coordinate {
-            row: self.robot_pos.0 as isize,
-            column: self.robot_pos.1 as isize,
+        let scale = |coordinate: Coordinate| Coordinate {
+            row: coordinate.row * height as isize,
+            column: coordinate.column * width as isize,
         };
-        assert_eq!(self.map.get_safe(&robot_coordinate), Some(&Robot), "Robot is not on the map");
+        let mut warehouse = Warehouse { map, boxes: Vec::new(), robot: scale(self.robot) };
+        warehouse.map.set_coord(&warehouse.robot, Robot).unwrap();
+        for warehouse_box in self.boxes.iter() {
+            warehouse.add_box(scale(warehouse_box.corner), warehouse_box.height * height as isize, warehouse_box.width * width as isize).unwrap();
+        }
+        warehouse
+    }
 
-        let robot_next = robot_coordinate + d;
-        if self.map.get_safe(&robot_next).is_none() {
-            return false;
+    fn add_box(&mut self, corner: Coordinate, height: isize, width: isize) -> Result<()> {
+        let warehouse_box = WarehouseBox { corner, height, width };
+        if height < 1 || width < 1 {
+            bail!("Box at {} has no area", corner);
+        }
+        if let Some(cell) = warehouse_box.cells().find(|cell| self.map.get_safe(cell) != Some(&Empty)) {
+            bail!("Box at {} does not fit, {} is taken", corner, cell);
         }
 
-        if self.map.get_safe(&(robot_next)) == Some(&Box) {
-            let mut curr = robot_coordinate + d;
-            while self.map.get_safe(&curr) == Some(&Box) {
-                curr = curr + d;
-            }
+        let id = self.boxes.len();
+        for cell in warehouse_box.cells() {
+            self.map.set_coord(&cell, Box(id)).unwrap();
+        }
+        self.boxes.push(warehouse_box);
+        Ok(())
+    }
 
-            // Check if the space after the last box is empty
-            if self.map.get_safe(&curr) != Some(&Empty) {
-                return false;
+    // Boxes moved by pushing the robot in `direction`, None when a wall or the map's edge blocks any of them
+    fn boxes_to_push(&self, direction: &Direction) -> Option<Vec<usize>> {
+        let d = direction.to_offset();
+        let mut pushed = Vec::new();
+        let mut visited_boxes: HashSet<usize> = HashSet::new();
+        let mut spaces = vec![self.robot + d];
+        while let Some(space) = spaces.pop() {
+            match self.map.get_safe(&space)? {
+                Wall => return None,
+                Empty => {}
+                &Box(id) => {
+                    if visited_boxes.insert(id) {
+                        pushed.push(id);
+                        spaces.extend(self.boxes[id].cells().map(|cell| cell + d));
+                    }
+                }
+                Robot => panic!("No actors to push the robot {}", space),
             }
-
-            // Put the first box to the empty place
-            self.map.set_coord(&curr, Box).unwrap();
-            self.map.set_coord(&(robot_next), Empty).unwrap();
         }
+        Some(pushed)
+    }
 
-        if self.map.get_safe(&(robot_next)) != Some(&Empty) {
-            return false
+    fn shift_boxes(&mut self, ids: &[usize], offset: (isize, isize)) {
+        for &id in ids {
+            for cell in self.boxes[id].cells() {
+                self.map.set_coord(&cell, Empty).unwrap();
+            }
         }
+        for &id in ids {
+            self.boxes[id].corner = self.boxes[id].corner + offset;
+            for cell in self.boxes[id].cells() {
+                self.map.set_coord(&cell, Box(id)).unwrap();
+            }
+        }
+    }
 
-        self.map.set_coord(&robot_coordinate, Empty).unwrap();
-        self.map.set_coord(&(robot_next), Robot).unwrap();
-        self.robot_pos = (robot_next.row as usize, robot_next.column as usize);
-
-        true
+    fn shift_robot(&mut self, offset: (isize, isize)) {
+        assert_eq!(self.map.get_safe(&self.robot), Some(&Robot), "Robot is not on the map");
+        self.map.set_coord(&self.robot, Empty).unwrap();
+        self.robot = self.robot + offset;
+        self.map.set_coord(&self.robot, Robot).unwrap();
     }
 
     fn move_robot(&mut self, moves: &Vec<Direction>) {
         for direction in moves {
-            self.try_move(direction);
+            self.step(direction);
         }
     }
 
     fn calculate_gps_coordinates(&self) -> usize {
-        let mut coordinates = 0;
-        for y in 0..self.map.num_rows() {
-            for x in 0..self.map.num_columns() {
-                if self.map.get(y, x).unwrap() == &Box {
-                    coordinates += 100 * y + x;
-                }
-            }
-        }
-        coordinates
+        self.boxes.iter()
+            .map(|warehouse_box| (100 * warehouse_box.corner.row + warehouse_box.corner.column) as usize)
+            .sum()
     }
 
     #[allow(dead_code)]
     fn display(&self) {
-        for y in 0..self.map.num_rows() {
-            for x in 0..self.map.num_columns() {
-                print!("{}", match self.map.get(y, x).unwrap() {
-                    Wall => '#',
-                    Empty => '.',
-                    Box => 'O',
-                    Robot => '@',
-                });
-            }
-            println!();
-        }
+        print!("{}", self);
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum ExtendedTile {
-    Wall,
-    Empty,
-    BoxLeft,
-    BoxRight,
-    Robot,
-}
-
-#[derive(Debug, Clone)]
-struct ExtendedWarehouse {
-    map: Array2D<ExtendedTile>,
-    robot_pos: (usize, usize),
-}
+impl Replayable for Warehouse {
+    // Checks the whole push first and only then moves the robot with the boxes
+    fn step(&mut self, direction: &Direction) -> MoveRecord {
+        let Some(ids) = self.boxes_to_push(direction) else {
+            return MoveRecord { direction: *direction, moved: false, pushed: Vec::new() };
+        };
 
-//noinspection DuplicatedCode
-impl ExtendedWarehouse {
-    fn from_warehouse(warehouse: &Warehouse) -> Self {
-        let rows = warehouse.map.num_rows();
-        let cols = warehouse.map.num_columns();
-
-        let mut extended_rows: Vec<Vec<ExtendedTile>> = Vec::with_capacity(rows);
-        for y in 0..rows {
-            let mut extended_row = Vec::with_capacity(cols * 2);
-            for x in 0..cols {
-                match warehouse.map.get(y, x).unwrap() {
-                    Wall => {
-                        extended_row.push(ExtendedTile::Wall);
-                        extended_row.push(ExtendedTile::Wall);
-                    },
-                    Empty => {
-                        extended_row.push(ExtendedTile::Empty);
-                        extended_row.push(ExtendedTile::Empty);
-                    },
-                    Box => {
-                        extended_row.push(ExtendedTile::BoxLeft);
-                        extended_row.push(ExtendedTile::BoxRight);
-                    },
-                    Robot => {
-                        extended_row.push(ExtendedTile::Robot);
-                        extended_row.push(ExtendedTile::Empty);
-                    }
-                }
-            }
+        let pushed = ids.iter().map(|&id| self.boxes[id].corner).collect();
+        let d = direction.to_offset();
+        self.shift_boxes(&ids, d);
+        self.shift_robot(d);
+        MoveRecord { direction: *direction, moved: true, pushed }
+    }
 
-            extended_rows.push(extended_row);
+    fn undo(&mut self, record: &MoveRecord) {
+        if !record.moved {
+            return;
         }
+        let d = record.direction.to_offset();
+        let ids: Vec<usize> = record.pushed.iter()
+            .map(|&corner| match self.map.get_safe(&(corner + d)) {
+                Some(&Box(id)) => id,
+                tile => panic!("Expected a pushed box at {}, found {:?}", corner + d, tile),
+            })
+            .collect();
+
+        let back = record.direction.opposite_direction().to_offset();
+        self.shift_robot(back);
+        self.shift_boxes(&ids, back);
+    }
+}
 
-        let robot_pos = (
-            warehouse.robot_pos.0,
-            warehouse.robot_pos.1 * 2
-        );
+// Moves applied one at a time, each step can be taken back
+struct Replay<W: Replayable> {
+    warehouse: W,
+    moves: Vec<Direction>,
+    history: Vec<MoveRecord>,
+}
 
-        ExtendedWarehouse {
-            map: Array2D::from_rows(&extended_rows).unwrap(),
-            robot_pos,
-        }
+impl<W: Replayable> Replay<W> {
+    fn new(warehouse: W, moves: Vec<Direction>) -> Replay<W> {
+        Replay { warehouse, moves, history: Vec::new() }
     }
 
-    fn push_box(
-        &mut self,
-        is_try: bool,
-        coordinate: &Coordinate,
-        direction: &Direction,
-    ) -> bool {
-        assert_eq!(self.map.get_safe(&coordinate), Some(&ExtendedTile::BoxLeft), "Expected to be box left corner {}", coordinate);
-
-        let d = direction.to_offset();
-        let spaces = match direction {
-            &Up | &Down => Vec::from([*coordinate + d, *coordinate + (0, 1) + d]),
-            &Right => Vec::from([*coordinate + (0, 1) + d]),
-            &Left => Vec::from([*coordinate + d])
-        };
+    fn forward(&mut self) -> Option<&MoveRecord> {
+        let direction = self.moves.get(self.history.len())?;
+        let record = self.warehouse.step(direction);
+        self.history.push(record);
+        self.history.last()
+    }
 
-        if !self.push(is_try, &spaces, direction) {
-            if !is_try {
-                panic!("Cannot push the box {}", coordinate);
-            }
-            return false;
-        }
+    fn backward(&mut self) -> Option<MoveRecord> {
+        let record = self.history.pop()?;
+        self.warehouse.undo(&record);
+        Some(record)
+    }
 
-        if !is_try {
-            self.map.set_coord(&coordinate, ExtendedTile::Empty).unwrap();
-            self.map.set_coord(&(*coordinate + (0, 1)), ExtendedTile::Empty).unwrap();
-            self.map.set_coord(&(*coordinate + d), ExtendedTile::BoxLeft).unwrap();
-            self.map.set_coord(&(*coordinate + (0, 1) + d), ExtendedTile::BoxRight).unwrap();
-        }
+    fn rewind(&mut self) {
+        while self.backward().is_some() {}
+    }
 
-        true
+    fn run(&mut self) {
+        while self.forward().is_some() {}
     }
 
-    fn push_robot(&mut self, is_try: bool, direction: &Direction) -> bool {
-        let robot_coordinate = Coordinate {
-            row: self.robot_pos.0 as isize,
-            column: self.robot_pos.1 as isize,
-        };
-        assert_eq!(self.map.get_safe(&robot_coordinate), Some(&ExtendedTile::Robot), "Robot is not on the map");
-        let desired_robot_coordinate = robot_coordinate + direction.to_offset();
-        if !self.push(is_try, &[desired_robot_coordinate].to_vec(), direction) {
-            if !is_try {
-                panic!("Cannot push the robot {}", &robot_coordinate);
-            }
-            return false;
-        }
+    // Number of applied moves
+    fn position(&self) -> usize {
+        self.history.len()
+    }
 
-        if !is_try {
-            self.map.set_coord(&robot_coordinate, ExtendedTile::Empty).unwrap();
-            self.map.set_coord(&(desired_robot_coordinate), ExtendedTile::Robot).unwrap();
-            self.robot_pos = (desired_robot_coordinate.row as usize, desired_robot_coordinate.column as usize);
-        }
+    fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
 
-        true
+    fn frame(&self) -> String {
+        self.warehouse.to_string()
     }
 
-    fn push(&mut self, is_try: bool, spaces: &Vec<Coordinate>, direction: &Direction) -> bool {
-        let mut visited_boxes: HashSet<Coordinate> = HashSet::new();
-        for space in spaces {
-            if let Some(tile) = self.map.get_safe(space) {
-                match tile {
-                    ExtendedTile::Wall => {
-                        if !is_try {
-                            panic!("Cannot push the wall {}", space);
-                        }
-                        return false;
-                    }
-                    ExtendedTile::Empty => {}
-                    ExtendedTile::BoxLeft => {
-                        if !visited_boxes.contains(space) {
-                            visited_boxes.insert(space.clone());
-                            if !self.push_box(is_try, space, direction) {
-                                return false
-                            }
-                        }
-                    }
-                    ExtendedTile::BoxRight => {
-                        let box_coordinate = *space + (0, -1);
-                        if !visited_boxes.contains(&box_coordinate) {
-                            visited_boxes.insert(box_coordinate.clone());
-                            if !self.push_box(is_try, &box_coordinate, direction) {
-                                return false
-                            }
-                        }
-                    }
-                    ExtendedTile::Robot => {
-                        panic!("No actors to push the robot {}", space);
-                    }
-                }
-            } else {
-                if !is_try {
-                    panic!("Cannot push outside the map {}", space);
-                }
-                return false;
-            }
+    // The initial frame and a frame after every move, the replay ends at the last move
+    fn frames(&mut self) -> Vec<String> {
+        self.rewind();
+        let mut frames = vec![self.frame()];
+        while self.forward().is_some() {
+            frames.push(self.frame());
         }
-        true
+        frames
     }
 
-    fn move_robot(&mut self, moves: &Vec<Direction>) {
-        for direction in moves {
-            if self.push_robot(true, direction) {
-                self.push_robot(false, direction);
+    #[allow(dead_code)]
+    fn write_text<T: Write>(&mut self, writer: &mut T) -> Result<()> {
+        for (i, frame) in self.frames().iter().enumerate() {
+            if i == 0 {
+                writeln!(writer, "Initial state:")?;
+            } else {
+                writeln!(writer, "Move {}: {}", i, self.history[i - 1])?;
             }
+            writeln!(writer, "{}", frame)?;
         }
+        Ok(())
     }
 
-    fn calculate_gps_coordinates(&self) -> usize {
-        let mut coordinates = 0;
-        for y in 0..self.map.num_rows() {
-            for x in 0..self.map.num_columns() {
-                if self.map.get(y, x).unwrap() == &ExtendedTile::BoxLeft {
-                    coordinates += 100 * y + x;
+    // Animated SVG showing each frame for `frame_seconds`, then looping
+    #[allow(dead_code)]
+    fn write_svg<T: Write>(&mut self, writer: &mut T, frame_seconds: f64) -> Result<()> {
+        const CELL: usize = 10;
+        let frames = self.frames();
+        let rows: Vec<&str> = frames[0].lines().collect();
+        let (width, height) = (rows.iter().map(|row| row.len()).max().unwrap_or(0), rows.len());
+        let total = frame_seconds * frames.len() as f64;
+
+        writeln!(writer, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#, width * CELL, height * CELL)?;
+        for (i, frame) in frames.iter().enumerate() {
+            writeln!(writer, r#"<g visibility="hidden">"#)?;
+            writeln!(
+                writer,
+                r#"<animate attributeName="visibility" values="hidden;visible;hidden" keyTimes="0;{:.6};{:.6}" dur="{}s" calcMode="discrete" repeatCount="indefinite"/>"#,
+                i as f64 / frames.len() as f64, (i + 1) as f64 / frames.len() as f64, total
+            )?;
+            for (y, row) in frame.lines().enumerate() {
+                for (x, tile) in row.chars().enumerate() {
+                    let color = match tile {
+                        '#' => "gray",
+                        'O' | '[' | '=' | ']' => "peru",
+                        '@' => "crimson",
+                        _ => continue,
+                    };
+                    writeln!(writer, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#, x * CELL, y * CELL, CELL, CELL, color)?;
                 }
             }
+            writeln!(writer, "</g>")?;
         }
-        coordinates
+        writeln!(writer, "</svg>")?;
+        Ok(())
     }
 }
 
-impl Display for ExtendedWarehouse {
+impl Display for Warehouse {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         for y in 0..self.map.num_rows() {
             for x in 0..self.map.num_columns() {
                 write!(f, "{}", match self.map.get(y, x).unwrap() {
-                    ExtendedTile::Wall => '#',
-                    ExtendedTile::Empty => '.',
-                    ExtendedTile::BoxLeft => '[',
-                    ExtendedTile::BoxRight => ']',
-                    ExtendedTile::Robot => '@',
+                    Wall => '#',
+                    Empty => '.',
+                    Box(id) => {
+                        let warehouse_box = &self.boxes[*id];
+                        let offset = x as isize - warehouse_box.corner.column;
+                        if warehouse_box.width == 1 {
+                            'O'
+                        } else if offset == 0 {
+                            '['
+                        } else if offset == warehouse_box.width - 1 {
+                            ']'
+                        } else {
+                            '='
+                        }
+                    }
+                    Robot => '@',
                 })?;
             }
             writeln!(f)?;
@@ -295,47 +345,86 @@ impl Display for ExtendedWarehouse {
     }
 }
 
-fn parse_warehouse(input: &str) -> Warehouse {
+fn parse_warehouse(input: &str) -> Result<Warehouse> {
     let mut map: Vec<Vec<Tile>> = Vec::new();
-    let mut robot_pos = (0, 0);
+    let mut boxes = Vec::new();
+    let mut robots = Vec::new();
 
     for (y, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            break;
+        }
         let mut row = Vec::new();
         for (x, ch) in line.chars().enumerate() {
+            let coordinate = Coordinate { row: y as isize, column: x as isize };
             let tile = match ch {
                 '#' => Wall,
                 '.' => Empty,
-                'O' => Box,
+                'O' => {
+                    boxes.push(WarehouseBox { corner: coordinate, height: 1, width: 1 });
+                    Box(boxes.len() - 1)
+                },
                 '@' => {
-                    robot_pos = (y, x);
+                    robots.push(coordinate);
                     Robot
                 },
-                _ => continue,
+                _ => bail!("Unknown character '{}' at row {}, column {}", ch, y + 1, x + 1),
             };
             row.push(tile);
         }
-        if !row.is_empty() {
-            map.push(row);
-        } else {
-            break;
+        if let Some(first_row) = map.first() {
+            if row.len() != first_row.len() {
+                bail!("Row {} has {} tiles, expected {}", y + 1, row.len(), first_row.len());
+            }
         }
+        map.push(row);
     }
 
-    Warehouse {
-        map: Array2D::from_rows(map.as_slice()).unwrap(),
-        robot_pos
+    if map.is_empty() {
+        bail!("Warehouse is empty");
+    }
+    let robot = match robots.as_slice() {
+        [] => bail!("No robot in the warehouse"),
+        [robot] => *robot,
+        [first, second, ..] => bail!(
+            "Several robots: at row {}, column {} and at row {}, column {}",
+            first.row + 1, first.column + 1, second.row + 1, second.column + 1
+        ),
+    };
+
+    // The pushes rely on the warehouse being closed
+    let (rows, columns) = (map.len(), map[0].len());
+    for (y, row) in map.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            if y != 0 && y != rows - 1 && x != 0 && x != columns - 1 {
+                continue;
+            }
+            match tile {
+                Wall => {}
+                Box(_) => bail!("Box at row {}, column {} touches the outer wall", y + 1, x + 1),
+                _ => bail!("Outer wall has a gap at row {}, column {}", y + 1, x + 1),
+            }
+        }
     }
+
+    Ok(Warehouse {
+        map: Array2D::from_rows(map.as_slice())?,
+        boxes,
+        robot,
+    })
 }
 
-fn parse_moves(input: &str) -> Vec<Direction> {
+// Moves may be split over several lines, positions count the moves only
+fn parse_moves(input: &str) -> Result<Vec<Direction>> {
     input.chars()
-        .filter(|&c| matches!(c, '^' | 'v' | '<' | '>'))
-        .map(|c| match c {
-            '^' => Up,
-            'v' => Down,
-            '<' => Left,
-            '>' => Right,
-            _ => unreachable!(),
+        .filter(|c| !c.is_whitespace())
+        .enumerate()
+        .map(|(i, c)| match c {
+            '^' => Ok(Up),
+            'v' => Ok(Down),
+            '<' => Ok(Left),
+            '>' => Ok(Right),
+            _ => Err(anyhow!("Unknown move '{}' at position {}", c, i + 1)),
         })
         .collect()
 }
@@ -344,25 +433,39 @@ fn parse_input<R: BufRead>(mut reader: R) -> Result<(Warehouse, Vec<Direction>)>
     let mut input_string = String::new();
     reader.read_to_string(&mut input_string)?;
 
-    let warehouse = parse_warehouse(input_string.as_str());
-    let moves = parse_moves(input_string.as_str());
+    // The map ends at the first blank line, whatever the line endings
+    let mut lines = input_string.lines();
+    let map: Vec<&str> = lines.by_ref().take_while(|line| !line.trim().is_empty()).collect();
+    let warehouse = parse_warehouse(&map.join("\n"))?;
+    let moves = parse_moves(&lines.collect::<Vec<_>>().join("\n"))?;
 
     Ok((warehouse, moves))
 }
 
-//noinspection DuplicatedCode
-fn part1<R: BufRead>(reader: R) -> Result<i64> {
-    let (mut warehouse, moves) = parse_input(reader)?;
+// Tiles are scaled to `height` x `width` blocks before the robot moves
+fn solve_ext<R: BufRead>(reader: R, height: usize, width: usize) -> Result<i64> {
+    let (warehouse, moves) = parse_input(reader)?;
+    let mut warehouse = warehouse.scaled(height, width);
     warehouse.move_robot(&moves);
     Ok(warehouse.calculate_gps_coordinates() as i64)
 }
 
-//noinspection DuplicatedCode
-fn part2<R: BufRead>(reader: R) -> Result<i64> {
+// What the robot's moves did in the wide warehouse
+fn part2_history<R: BufRead>(reader: R) -> Result<String> {
     let (warehouse, moves) = parse_input(reader)?;
-    let mut warehouse = ExtendedWarehouse::from_warehouse(&warehouse);
-    warehouse.move_robot(&moves);
-    Ok(warehouse.calculate_gps_coordinates() as i64)
+    let mut replay = Replay::new(warehouse.scaled(1, 2), moves);
+    replay.run();
+    let blocked = replay.history().iter().filter(|record| !record.moved).count();
+    let pushes = replay.history().iter().filter(|record| !record.pushed.is_empty()).count();
+    Ok(format!("{} moves, {} blocked, {} pushing boxes", replay.position(), blocked, pushes))
+}
+
+fn part1<R: BufRead>(reader: R) -> Result<i64> {
+    solve_ext(reader, 1, 1)
+}
+
+fn part2<R: BufRead>(reader: R) -> Result<i64> {
+    solve_ext(reader, 1, 2)
 }
 
 //#region
@@ -374,6 +477,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), part2_history)?;
     Ok(())
 }
 
@@ -390,10 +494,279 @@ mod tests {
     use indoc::indoc;
     use super::*;
 
+    #[cfg(test)]
+    mod replay_tests {
+        use super::*;
+
+        const SMALL: &str = indoc! {"
+            #######
+            #...#.#
+            #.....#
+            #..OO@#
+            #..O..#
+            #.....#
+            #######
+
+            <vv<<^^<<^^
+        "};
+
+        #[test]
+        fn test_history() {
+            let (warehouse, moves) = parse_input(BufReader::new(SMALL.as_bytes())).unwrap();
+            let mut replay = Replay::new(warehouse.scaled(1, 2), moves);
+            replay.run();
+
+            assert_eq!(11, replay.position());
+            assert_eq!("11 moves, 1 blocked, 3 pushing boxes", part2_history(BufReader::new(SMALL.as_bytes())).unwrap());
+            assert_eq!(
+                vec!["< moved, pushed 2 boxes", "v moved", "v moved", "< moved", "< moved", "^ moved, pushed 3 boxes"],
+                replay.history().iter().take(6).map(|record| record.to_string()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                indoc! {"
+                    ##############
+                    ##...[].##..##
+                    ##...@.[]...##
+                    ##....[]....##
+                    ##..........##
+                    ##..........##
+                    ##############
+                "},
+                replay.frame()
+            );
+            assert_eq!(618, replay.warehouse.calculate_gps_coordinates());
+        }
+
+        #[test]
+        fn test_backward() {
+            let (warehouse, moves) = parse_input(BufReader::new(SMALL.as_bytes())).unwrap();
+            let initial = warehouse.scaled(1, 2).to_string();
+            let mut replay = Replay::new(warehouse.scaled(1, 2), moves.clone());
+            let frames = replay.frames();
+            assert_eq!(12, frames.len());
+
+            for position in (0..frames.len()).rev() {
+                assert_eq!(frames[position], replay.frame(), "position {}", position);
+                replay.backward();
+            }
+            assert_eq!(initial, replay.frame());
+            assert_eq!(None, replay.backward());
+
+            let mut narrow = Replay::new(warehouse, moves);
+            let narrow_frames = narrow.frames();
+            narrow.rewind();
+            assert_eq!(narrow_frames[0], narrow.frame());
+            assert_eq!(Some("< moved, pushed 2 boxes".to_string()), narrow.forward().map(|record| record.to_string()));
+            assert_eq!(narrow_frames[1], narrow.frame());
+        }
+
+        #[test]
+        fn test_export() {
+            let (warehouse, _) = parse_input(BufReader::new(SMALL.as_bytes())).unwrap();
+            let mut replay = Replay::new(warehouse, vec![Left, Up]);
+
+            let mut text = Vec::new();
+            replay.write_text(&mut text).unwrap();
+            assert_eq!(
+                indoc! {"
+                    Initial state:
+                    #######
+                    #...#.#
+                    #.....#
+                    #..OO@#
+                    #..O..#
+                    #.....#
+                    #######
+
+                    Move 1: < moved, pushed 2 boxes
+                    #######
+                    #...#.#
+                    #.....#
+                    #.OO@.#
+                    #..O..#
+                    #.....#
+                    #######
+
+                    Move 2: ^ moved
+                    #######
+                    #...#.#
+                    #...@.#
+                    #.OO..#
+                    #..O..#
+                    #.....#
+                    #######
+
+                "},
+                String::from_utf8(text).unwrap()
+            );
+
+            let mut svg = Vec::new();
+            replay.write_svg(&mut svg, 0.5).unwrap();
+            let svg = String::from_utf8(svg).unwrap();
+            assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="70" height="70">"#));
+            assert_eq!(3, svg.matches("<animate ").count());
+            assert_eq!(3, svg.matches("crimson").count());
+        }
+    }
+
+    #[cfg(test)]
+    mod shape_tests {
+        use super::*;
+
+        #[test]
+        fn test_mixed_shapes() {
+            let mut warehouse = parse_warehouse(indoc! {"
+                ########
+                #......#
+                #......#
+                #......#
+                #..@...#
+                ########
+            "}).unwrap();
+            warehouse.add_box(Coordinate { row: 1, column: 1 }, 1, 3).unwrap();
+            warehouse.add_box(Coordinate { row: 2, column: 3 }, 2, 2).unwrap();
+            warehouse.add_box(Coordinate { row: 1, column: 4 }, 1, 1).unwrap();
+            assert!(warehouse.add_box(Coordinate { row: 3, column: 2 }, 1, 2).is_err());
+            assert!(warehouse.add_box(Coordinate { row: 4, column: 6 }, 1, 2).is_err());
+            assert_eq!(
+                indoc! {"
+                    ########
+                    #[=]O..#
+                    #..[]..#
+                    #..[]..#
+                    #..@...#
+                    ########
+                "},
+                warehouse.to_string()
+            );
+
+            let mut replay = Replay::new(warehouse, parse_moves("^>>^<^^<^v").unwrap());
+            replay.run();
+            assert_eq!(
+                vec![
+                    "^ blocked", "> moved", "> moved", "^ moved", "< moved, pushed 1 box",
+                    "^ moved", "^ blocked", "< moved, pushed 1 box", "^ blocked", "v moved",
+                ],
+                replay.history().iter().map(|record| record.to_string()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                indoc! {"
+                    ########
+                    #[=]O..#
+                    #[]....#
+                    #[]@...#
+                    #......#
+                    ########
+                "},
+                replay.frame()
+            );
+            assert_eq!(406, replay.warehouse.calculate_gps_coordinates());
+        }
+
+        #[test]
+        fn test_blocked_push_is_atomic() {
+            let mut warehouse = parse_warehouse(indoc! {"
+                #######
+                #.#...#
+                #.....#
+                #.....#
+                #..@..#
+                #######
+            "}).unwrap().scaled(1, 2);
+            warehouse.add_box(Coordinate { row: 3, column: 6 }, 1, 2).unwrap();
+            warehouse.add_box(Coordinate { row: 2, column: 5 }, 1, 2).unwrap();
+            warehouse.add_box(Coordinate { row: 2, column: 7 }, 1, 2).unwrap();
+            let before = warehouse.to_string();
+            // The right box could move up while the left one hits the wall
+            let record = warehouse.step(&Up);
+            assert_eq!("^ blocked", record.to_string());
+            assert_eq!(before, warehouse.to_string());
+        }
+
+        #[test]
+        fn test_scaled() {
+            let warehouse = parse_warehouse(indoc! {"
+                #######
+                #...#.#
+                #.....#
+                #..OO@#
+                #..O..#
+                #.....#
+                #######
+            "}).unwrap();
+            let mut wide = warehouse.scaled(1, 3);
+            assert_eq!(Some("###......[=][=]@..###"), wide.to_string().lines().nth(3));
+            assert_eq!("< moved, pushed 2 boxes", wide.step(&Left).to_string());
+            assert_eq!(Some("###.....[=][=]@...###"), wide.to_string().lines().nth(3));
+
+            let square = warehouse.scaled(2, 2);
+            assert_eq!(
+                vec!["##....[][]@.##", "##....[][]..##"],
+                square.to_string().lines().skip(6).take(2).collect::<Vec<_>>()
+            );
+            assert_eq!(3, square.boxes.len());
+            assert_eq!(
+                warehouse.calculate_gps_coordinates() * 3,
+                warehouse.scaled(2, 1).calculate_gps_coordinates() + warehouse.scaled(1, 2).calculate_gps_coordinates()
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod validation_tests {
+        use super::*;
+
+        fn parse_error(input: &str) -> String {
+            parse_input(BufReader::new(input.as_bytes())).unwrap_err().to_string()
+        }
+
+        #[test]
+        fn test_robots() {
+            assert_eq!("No robot in the warehouse", parse_error("####\n#..#\n####\n\n<>"));
+            assert_eq!(
+                "Several robots: at row 2, column 2 and at row 3, column 3",
+                parse_error("####\n#@.#\n#.@#\n####\n\n<>")
+            );
+        }
+
+        #[test]
+        fn test_outer_wall() {
+            assert_eq!("Box at row 1, column 3 touches the outer wall", parse_error("##O#\n#@.#\n####\n\n<>"));
+            assert_eq!("Outer wall has a gap at row 2, column 4", parse_error("####\n#@..\n####\n\n<>"));
+        }
+
+        #[test]
+        fn test_tiles() {
+            assert_eq!("Row 2 has 3 tiles, expected 4", parse_error("####\n#@#\n####\n\n<>"));
+            assert_eq!("Unknown character 'x' at row 2, column 3", parse_error("####\n#@x#\n####\n\n<>"));
+            assert_eq!("Warehouse is empty", parse_error("\n\n<>"));
+        }
+
+        #[test]
+        fn test_moves() {
+            assert_eq!("Unknown move 'x' at position 4", parse_error("####\n#@.#\n####\n\n<>\n^x<"));
+            let (_, moves) = parse_input(BufReader::new("####\n#@.#\n####\n\n<>\n^v\n".as_bytes())).unwrap();
+            assert_eq!(vec![Left, Right, Up, Down], moves);
+            let (_, moves) = parse_input(BufReader::new("####\n#@.#\n####\n".as_bytes())).unwrap();
+            assert!(moves.is_empty());
+        }
+
+        #[test]
+        fn test_crlf() {
+            let input = "########\n#..O.O.#\n##@.O..#\n#...O..#\n#.#.O..#\n#...O..#\n#......#\n########\n\n<^^>>>vv<v>>v<<\n";
+            let (warehouse, moves) = parse_input(BufReader::new(input.replace('\n', "\r\n").as_bytes())).unwrap();
+            assert_eq!(15, moves.len());
+            assert_eq!(8, warehouse.map.num_rows());
+            assert_eq!(2028, part1(BufReader::new(input.replace('\n', "\r\n").as_bytes())).unwrap());
+            assert_eq!("Unknown move 'x' at position 2", parse_error("####\r\n#@.#\r\n####\r\n\r\n<x\r\n"));
+        }
+    }
+
     //noinspection SpellCheckingInspection
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -450,7 +823,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(1465523, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -458,6 +831,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: i64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -473,8 +847,8 @@ mod tests {
                 #..O..#
                 #.....#
                 #######
-            "});
-            let warehouse = ExtendedWarehouse::from_warehouse(&warehouse);
+            "}).unwrap();
+            let warehouse = warehouse.scaled(1, 2);
             let display_string = format!("{}", warehouse);
             assert_eq!(
                 indoc! {"
@@ -540,7 +914,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!(1471049, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
@@ -259,6 +259,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: u64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -329,7 +330,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(107468, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -337,6 +338,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: u64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -394,7 +396,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!(533, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
@@ -199,6 +199,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: &str, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -296,7 +297,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!("6,1,6,4,2,4,7,3,5", run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
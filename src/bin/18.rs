@@ -129,6 +129,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: u64, input: &str, rows: usize, cols: usize, bytes_len: usize) {
             assert_eq!(expect, part1_full(BufReader::new(input.as_bytes()), rows, cols, bytes_len).unwrap());
@@ -171,7 +172,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(340, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -179,6 +180,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: &str, input: &str, rows: usize, cols: usize) {
             assert_eq!(expect, part2_full(BufReader::new(input.as_bytes()), rows, cols).unwrap());
@@ -221,7 +223,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!("34,32", run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
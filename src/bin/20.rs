@@ -247,6 +247,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str, min_saving: i64) {
             assert_eq!(expect, part1_general(BufReader::new(input.as_bytes()), min_saving).unwrap());
@@ -325,7 +326,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(1327, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -334,6 +335,7 @@ mod tests {
     mod part2_tests {
         use itertools::Itertools;
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_cheats(saving: i64, expected_cheats: usize) {
             let input = indoc! {"
@@ -383,7 +385,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!(985737, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
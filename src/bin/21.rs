@@ -1,4 +1,5 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::iter::{once, repeat_n};
 use advent_of_code2024_rust::{day, run_on_day_input};
 use anyhow::*;
 use std::io::{BufRead};
@@ -40,9 +41,19 @@ impl DirectionKeyboardAction {
             _ => panic!("Invalid char: {}", c)
         }
     }
+
+    fn to_char(self) -> char {
+        match self {
+            DirectionKeyboardAction::Up => '^',
+            DirectionKeyboardAction::Right => '>',
+            DirectionKeyboardAction::Down => 'v',
+            DirectionKeyboardAction::Left => '<',
+            DirectionKeyboardAction::Press => 'A',
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct DirectionKeyboardCostMatrix {
     matrix: [[usize; 5]; 5],
 }
@@ -74,39 +85,104 @@ struct Point {
     y: i32,
 }
 
-#[derive(Debug)]
-struct NumericKeypad {
-    transition_cost: HashMap<(char, char), usize>
+const NUMERIC_KEYPAD: &str = "
++---+---+---+
+| 7 | 8 | 9 |
++---+---+---+
+| 4 | 5 | 6 |
++---+---+---+
+| 1 | 2 | 3 |
++---+---+---+
+    | 0 | A |
+    +---+---+
+";
+
+const DIRECTIONAL_KEYPAD: &str = "
+    +---+---+
+    | ^ | A |
++---+---+---+
+| < | v | > |
++---+---+---+
+";
+
+// Button positions read from an ASCII drawing, every `|`-delimited line is a row of buttons and
+// blank cells are gaps the robot arm can't pass over
+#[derive(Debug, Clone)]
+struct KeypadLayout {
+    buttons: HashMap<Point, char>,
 }
 
-impl NumericKeypad {
-    /*
-    +---+---+---+
-    | 7 | 8 | 9 |
-    +---+---+---+
-    | 4 | 5 | 6 |
-    +---+---+---+
-    | 1 | 2 | 3 |
-    +---+---+---+
-        | 0 | A |
-        +---+---+
-     */
-    fn numeric_keypad(control: &DirectionKeyboardCostMatrix) -> Self {
-        let buttons =
-            [
-                ((0, 0), '7'), ((1, 0), '8'), ((2, 0), '9'),
-                ((0, 1), '4'), ((1, 1), '5'), ((2, 1), '6'),
-                ((0, 2), '1'), ((1, 2), '2'), ((2, 2), '3'),
-                /* --------- */((1, 3), '0'), ((2, 3), 'A'),
-            ]
+impl KeypadLayout {
+    fn parse(drawing: &str) -> Result<Self> {
+        let rows: Vec<Vec<(usize, char)>> = drawing.lines()
+            .filter(|line| line.contains('|'))
+            .map(|line| line.chars().enumerate().collect())
+            .collect();
+
+        // A cell's column is the rank of its left `|` among the separators of all rows, so blank
+        // cells keep their place whatever the cell width
+        let columns: Vec<usize> = rows.iter().flatten()
+            .filter(|(_, c)| *c == '|')
+            .map(|(position, _)| *position)
+            .collect::<BTreeSet<_>>()
             .into_iter()
-            .map(|((x, y), val)| (Point { x, y }, val))
             .collect();
+        let mut buttons = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            let separators: Vec<usize> = row.iter().filter(|(_, c)| *c == '|').map(|(position, _)| *position).collect();
+            for cell in separators.windows(2) {
+                let label: Vec<char> = row[cell[0] + 1..cell[1]].iter().map(|(_, c)| *c).filter(|c| *c != ' ').collect();
+                let label = match label.as_slice() {
+                    [] => continue,
+                    [label] => *label,
+                    _ => bail!("Row {} has a label longer than one character", y + 1),
+                };
+                if buttons.values().any(|&known| known == label) {
+                    bail!("Button '{}' appears twice", label);
+                }
+                let x = columns.binary_search(&cell[0]).unwrap();
+                buttons.insert(Point { x: x as i32, y: y as i32 }, label);
+            }
+        }
 
-        let cost_matrix = build_cost_matrix(&buttons, control);
+        if !buttons.values().any(|&label| label == 'A') {
+            bail!("Keypad has no A button");
+        }
+        Ok(Self { buttons })
+    }
+
+    fn numeric() -> Self {
+        Self::parse(NUMERIC_KEYPAD).unwrap()
+    }
 
+    fn directional() -> Self {
+        Self::parse(DIRECTIONAL_KEYPAD).unwrap()
+    }
+
+    fn position(&self, label: char) -> Option<Point> {
+        self.buttons.iter().find(|(_, &known)| known == label).map(|(point, _)| *point)
+    }
+}
+
+// Presses on the controlling keypad for moving from one button to another and pressing it
+type PressPaths = HashMap<(char, char), Vec<DirectionKeyboardAction>>;
+
+#[derive(Debug)]
+struct NumericKeypad {
+    transition_cost: HashMap<(char, char), usize>,
+    transition_path: PressPaths,
+    // Directional keypads between the human and this keypad, the human's side first
+    controls: Vec<DirectionKeypad>,
+}
+
+impl NumericKeypad {
+    fn new(layout: &KeypadLayout, controls: Vec<DirectionKeypad>) -> Self {
+        let control = controls.last().map_or(&MANUAL_KEYBOARD_COST, |keypad| &keypad.press_cost);
+        let (transition_cost, transition_path) = build_cost_matrix(&layout.buttons, control);
         Self {
-            transition_cost: cost_matrix
+            transition_cost,
+            transition_path,
+            controls,
         }
     }
 }
@@ -115,38 +191,32 @@ impl NumericKeypad {
 // Represents different types of keypads
 #[derive(Debug)]
 struct DirectionKeypad {
-    press_cost: DirectionKeyboardCostMatrix
+    press_cost: DirectionKeyboardCostMatrix,
+    press_path: PressPaths,
 }
 
 impl DirectionKeypad {
-    /*
-        +---+---+
-        | ^ | A |
-    +---+---+---+
-    | < | v | > |
-    +---+---+---+
-     */
-    fn remote_directional_keypad(control: &DirectionKeyboardCostMatrix) -> Self {
-        let buttons: HashMap<Point, char> =
-            [
-                /* --------- */((1, 0), '^'), ((2, 0), 'A'),
-                ((0, 1), '<'), ((1, 1), 'v'), ((2, 1), '>'),
-            ]
-            .into_iter()
-            .map(|((x, y), val)| (Point { x, y }, val))
-            .collect();
+    fn new(layout: &KeypadLayout, control: &DirectionKeyboardCostMatrix) -> Result<Self> {
+        let labels: HashSet<char> = layout.buttons.values().copied().collect();
+        if labels != HashSet::from(['^', '>', 'v', '<', 'A']) {
+            bail!("Directional keypad needs exactly the buttons ^ > v < A");
+        }
 
-        let press_costs = build_cost_matrix(&buttons, control);
+        let (press_costs, press_path) = build_cost_matrix(&layout.buttons, control);
         let mut action_costs = DirectionKeyboardCostMatrix::new();
         for ((from, to), cost) in press_costs.iter() {
             let from_action = DirectionKeyboardAction::from_char(from);
             let to_action = DirectionKeyboardAction::from_char(to);
             action_costs.set(from_action, to_action, *cost);
         }
-
-        Self {
-            press_cost: action_costs
+        if press_costs.len() != 25 {
+            bail!("Some buttons of the directional keypad can't be reached");
         }
+
+        Ok(Self {
+            press_cost: action_costs,
+            press_path,
+        })
     }
 }
 
@@ -177,8 +247,12 @@ fn count_path_cost<T>(
     sum
 }
 
-fn build_cost_matrix(buttons: &HashMap<Point, char>, cost: &DirectionKeyboardCostMatrix) -> HashMap<(char, char), usize> {
+fn build_cost_matrix(
+    buttons: &HashMap<Point, char>,
+    cost: &DirectionKeyboardCostMatrix,
+) -> (HashMap<(char, char), usize>, PressPaths) {
     let mut cost_matrix: HashMap<(char, char), usize> = HashMap::new();
+    let mut path_matrix: PressPaths = HashMap::new();
 
     for (from_point, from_char) in buttons.iter() {
         let mut visited_path_length: HashMap<Point, usize> = HashMap::new();
@@ -195,6 +269,10 @@ fn build_cost_matrix(buttons: &HashMap<Point, char>, cost: &DirectionKeyboardCos
             let known_cost = cost_matrix.get(&from_to_path).unwrap_or(&usize::MAX);
             if cost < *known_cost {
                 cost_matrix.insert(from_to_path, cost);
+                path_matrix.insert(
+                    from_to_path,
+                    path[1..].iter().map(|(_, action)| *action).chain(once(DirectionKeyboardAction::Press)).collect()
+                );
                 visited_path_length.insert(last_point.clone(), path.len());
             }
 
@@ -215,25 +293,101 @@ fn build_cost_matrix(buttons: &HashMap<Point, char>, cost: &DirectionKeyboardCos
         }
     }
 
-    cost_matrix
+    (cost_matrix, path_matrix)
 }
 
-fn key_cost(code: &str, numeric_keypad: &NumericKeypad) -> i64 {
+fn key_cost(code: &str, numeric_keypad: &NumericKeypad) -> Result<i64> {
     let mut cost = 0;
     let mut previous = 'A';
 
     for c in code.chars() {
-        cost += numeric_keypad.transition_cost.get(&(previous, c)).unwrap();
+        let Some(transition_cost) = numeric_keypad.transition_cost.get(&(previous, c)) else {
+            bail!("Code {}: can't press '{}' after '{}'", code, c, previous);
+        };
+        cost += transition_cost;
         previous = c;
     }
 
-    cost as i64
+    Ok(cost as i64)
+}
+
+fn code_cost(code: &str, numeric_keypad: &NumericKeypad) -> Result<i64> {
+    let num = code.trim_end_matches('A').parse::<i64>()
+        .with_context(|| format!("Code {} is not a number followed by A", code))?;
+    let cost = key_cost(code, numeric_keypad)?;
+    Ok(num * cost)
 }
 
-fn code_cost(code: &str, numeric_keypad: &NumericKeypad) -> i64 {
-    let num = code.trim_end_matches('A').parse::<i64>().unwrap();
-    let cost = key_cost(code, numeric_keypad);
-    num * cost
+// Longest sequence `button_sequence` builds, the length grows exponentially with the chain depth
+const MAX_SEQUENCE_LENGTH: i64 = 1 << 20;
+
+// One of the cheapest sequences of presses on the human's keypad that types `code`
+fn button_sequence(code: &str, numeric_keypad: &NumericKeypad) -> Result<String> {
+    let length = key_cost(code, numeric_keypad)?;
+    if length > MAX_SEQUENCE_LENGTH {
+        bail!("Code {}: the sequence would have {} presses, more than {}", code, length, MAX_SEQUENCE_LENGTH);
+    }
+
+    let mut previous = 'A';
+    let mut actions = Vec::new();
+    for c in code.chars() {
+        actions.extend(numeric_keypad.transition_path[&(previous, c)].iter().copied());
+        previous = c;
+    }
+
+    // Each keypad's presses are made by moving the arm over it from the keypad before
+    for control in numeric_keypad.controls.iter().rev() {
+        let mut previous = 'A';
+        let mut control_actions = Vec::with_capacity(actions.len());
+        for action in actions {
+            control_actions.extend(control.press_path[&(previous, action.to_char())].iter().copied());
+            previous = action.to_char();
+        }
+        actions = control_actions;
+    }
+
+    Ok(actions.into_iter().map(DirectionKeyboardAction::to_char).collect())
+}
+
+// Presses `sequence` on the human's keypad and returns what the last robot types on `layout`
+fn simulate(
+    sequence: &str,
+    layout: &KeypadLayout,
+    directional_layout: &KeypadLayout,
+    number_of_robot_direction_keypads: usize,
+) -> Result<String> {
+    let keypads: Vec<&KeypadLayout> = repeat_n(directional_layout, number_of_robot_direction_keypads)
+        .chain(once(layout))
+        .collect();
+    let mut arms = keypads.iter()
+        .map(|keypad| keypad.position('A').context("Keypad has no A button"))
+        .collect::<Result<Vec<Point>>>()?;
+
+    let mut typed = String::new();
+    for (i, c) in sequence.chars().enumerate() {
+        if !"^>v<A".contains(c) {
+            bail!("Unknown button '{}' at position {}", c, i + 1);
+        }
+        let mut action = DirectionKeyboardAction::from_char(&c);
+        for (robot, keypad) in keypads.iter().enumerate() {
+            if !matches!(action, DirectionKeyboardAction::Press) {
+                arms[robot] = move_direction(&arms[robot], &action);
+                if !keypad.buttons.contains_key(&arms[robot]) {
+                    bail!("Press {} moves robot {} over a gap", i + 1, robot + 1);
+                }
+                break;
+            }
+
+            let label = keypad.buttons[&arms[robot]];
+            if robot == keypads.len() - 1 {
+                typed.push(label);
+            } else {
+                action = DirectionKeyboardAction::from_char(&label);
+            }
+        }
+    }
+
+    Ok(typed)
 }
 
 fn parse_input<R: BufRead>(reader: R) -> Vec<String> {
@@ -243,23 +397,28 @@ fn parse_input<R: BufRead>(reader: R) -> Vec<String> {
         .collect()
 }
 
-fn create_numpad_keyboard(number_of_robot_direction_keypads: usize) -> NumericKeypad {
-    let mut current_robot_keypad =
-        DirectionKeypad::remote_directional_keypad(&MANUAL_KEYBOARD_COST);
-
-    for _ in 2..=number_of_robot_direction_keypads {
-        current_robot_keypad = DirectionKeypad::remote_directional_keypad(&current_robot_keypad.press_cost);
+// The human's directional keypad drives a robot at the first of `number_of_robot_direction_keypads`
+// directional keypads, each of them drives a robot at the next one and the last robot types on `layout`
+fn create_numpad_keyboard(
+    layout: &KeypadLayout,
+    directional_layout: &KeypadLayout,
+    number_of_robot_direction_keypads: usize,
+) -> Result<NumericKeypad> {
+    let mut controls: Vec<DirectionKeypad> = Vec::with_capacity(number_of_robot_direction_keypads);
+    for _ in 0..number_of_robot_direction_keypads {
+        let control = controls.last().map_or(&MANUAL_KEYBOARD_COST, |keypad| &keypad.press_cost);
+        controls.push(DirectionKeypad::new(directional_layout, control)?);
     }
 
-    NumericKeypad::numeric_keypad(&current_robot_keypad.press_cost)
+    Ok(NumericKeypad::new(layout, controls))
 }
 
 fn create_numpad_keyboard_part1() -> NumericKeypad {
-    create_numpad_keyboard(2)
+    create_numpad_keyboard(&KeypadLayout::numeric(), &KeypadLayout::directional(), 2).unwrap()
 }
 
 fn create_numpad_keyboard_part2() -> NumericKeypad {
-    create_numpad_keyboard(25)
+    create_numpad_keyboard(&KeypadLayout::numeric(), &KeypadLayout::directional(), 25).unwrap()
 }
 
 
@@ -268,7 +427,7 @@ fn part1<R: BufRead>(reader: R) -> Result<i64> {
     let codes = parse_input(reader);
     let numpad = create_numpad_keyboard_part1();
 
-    Ok(codes.iter().map(|code| code_cost(code, &numpad)).sum())
+    codes.iter().map(|code| code_cost(code, &numpad)).sum()
 }
 
 //noinspection DuplicatedCode
@@ -276,13 +435,30 @@ fn part2<R: BufRead>(reader: R) -> Result<i64> {
     let codes = parse_input(reader);
     let numpad = create_numpad_keyboard_part2();
 
-    Ok(codes.iter().map(|code| code_cost(code, &numpad)).sum())
+    codes.iter().map(|code| code_cost(code, &numpad)).sum()
+}
+
+// The presses the human makes for each code in part 1, replayed through the robots as a check
+fn part1_sequences<R: BufRead>(reader: R) -> Result<String> {
+    let codes = parse_input(reader);
+    let (numeric, directional) = (KeypadLayout::numeric(), KeypadLayout::directional());
+    let numpad = create_numpad_keyboard(&numeric, &directional, 2)?;
+
+    let mut lines = Vec::with_capacity(codes.len());
+    for code in codes.iter() {
+        let sequence = button_sequence(code, &numpad)?;
+        let typed = simulate(&sequence, &numeric, &directional, 2)?;
+        ensure!(typed == *code, "Sequence {} for code {} types {}", sequence, code, typed);
+        lines.push(format!("{}: {}", code, sequence));
+    }
+    Ok(lines.join("\n"))
 }
 
 //#region
 
 fn part1_result() -> Result<()> {
     run_on_day_input(day!(), part1)?;
+    run_on_day_input(day!(), part1_sequences)?;
     Ok(())
 }
 
@@ -304,10 +480,142 @@ mod tests {
     use indoc::indoc;
     use super::*;
 
+    #[cfg(test)]
+    mod layout_tests {
+        use super::*;
+
+        #[test]
+        fn test_parse() {
+            let layout = KeypadLayout::numeric();
+            assert_eq!(11, layout.buttons.len());
+            assert_eq!(Some(&'7'), layout.buttons.get(&Point { x: 0, y: 0 }));
+            assert_eq!(Some(&'A'), layout.buttons.get(&Point { x: 2, y: 3 }));
+            assert_eq!(None, layout.buttons.get(&Point { x: 0, y: 3 }));
+
+            let gap = KeypadLayout::parse("| 1 |   | A |").unwrap();
+            assert_eq!(Some(&'A'), gap.buttons.get(&Point { x: 2, y: 0 }));
+            assert_eq!(2, gap.buttons.len());
+
+            let directional = KeypadLayout::directional();
+            assert_eq!(Some(&'^'), directional.buttons.get(&Point { x: 1, y: 0 }));
+            assert_eq!(Some(&'<'), directional.buttons.get(&Point { x: 0, y: 1 }));
+        }
+
+        #[test]
+        fn test_invalid_layouts() {
+            let error = |drawing: &str| KeypadLayout::parse(drawing).unwrap_err().to_string();
+            assert_eq!("Keypad has no A button", error("| 1 | 2 |"));
+            assert_eq!("Button '1' appears twice", error("| 1 | A |\n| 1 | 2 |"));
+            assert_eq!("Row 1 has a label longer than one character", error("| 10 | A |"));
+
+            let numeric = KeypadLayout::numeric();
+            assert!(create_numpad_keyboard(&numeric, &numeric, 1).is_err());
+            assert_eq!(
+                "Some buttons of the directional keypad can't be reached",
+                create_numpad_keyboard(&numeric, &KeypadLayout::parse("| ^ | A |   | < | v | > |").unwrap(), 1)
+                    .unwrap_err().to_string()
+            );
+            assert!(create_numpad_keyboard(&numeric, &KeypadLayout::parse("| ^ | A |\n\n| < | v | > |").unwrap(), 1).is_ok());
+        }
+
+        #[test]
+        fn test_chain_depth() {
+            let numeric = KeypadLayout::numeric();
+            let directional = KeypadLayout::directional();
+            let cost = |depth| key_cost("029A", &create_numpad_keyboard(&numeric, &directional, depth).unwrap()).unwrap();
+            assert_eq!(12, cost(0));
+            assert_eq!(28, cost(1));
+            assert_eq!(68, cost(2));
+        }
+
+        #[test]
+        fn test_custom_layouts() {
+            let gap = KeypadLayout::parse(indoc! {"
+                +---+---+---+
+                | 1 | 2 | 3 |
+                +---+---+---+
+                | 4 |   | A |
+                +---+---+---+
+            "}).unwrap();
+            let line = KeypadLayout::parse("| < | > | ^ | v | A |").unwrap();
+            let keypad = create_numpad_keyboard(&gap, &line, 0).unwrap();
+            assert_eq!(10, key_cost("4A", &keypad).unwrap());
+
+            let tiny = KeypadLayout::parse("| 1 | A |").unwrap();
+            let keypad = create_numpad_keyboard(&tiny, &line, 1).unwrap();
+            assert_eq!(18, key_cost("1A", &keypad).unwrap());
+            assert_eq!("Code 2A: can't press '2' after 'A'", key_cost("2A", &keypad).unwrap_err().to_string());
+        }
+    }
+
+    #[cfg(test)]
+    mod sequence_tests {
+        use super::*;
+
+        const CODES: [&str; 5] = ["029A", "980A", "179A", "456A", "379A"];
+
+        #[test]
+        fn test_simulate() {
+            let (numeric, directional) = (KeypadLayout::numeric(), KeypadLayout::directional());
+            assert_eq!(
+                "029A",
+                simulate("<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A", &numeric, &directional, 2).unwrap()
+            );
+            assert_eq!("029A", simulate("<A^A>^^AvvvA", &numeric, &directional, 0).unwrap());
+            assert_eq!("Press 2 moves robot 1 over a gap", simulate("<<A", &numeric, &directional, 0).unwrap_err().to_string());
+            assert_eq!("Press 2 moves robot 1 over a gap", simulate("<<", &numeric, &directional, 1).unwrap_err().to_string());
+            assert_eq!("Press 5 moves robot 2 over a gap", simulate("v<<AA", &numeric, &directional, 1).unwrap_err().to_string());
+            assert_eq!("Unknown button 'x' at position 2", simulate("<x", &numeric, &directional, 0).unwrap_err().to_string());
+        }
+
+        #[test]
+        fn test_shortest_sequences() {
+            let (numeric, directional) = (KeypadLayout::numeric(), KeypadLayout::directional());
+            for depth in 0..=3 {
+                let keypad = create_numpad_keyboard(&numeric, &directional, depth).unwrap();
+                for code in CODES {
+                    let sequence = button_sequence(code, &keypad).unwrap();
+                    assert_eq!(key_cost(code, &keypad).unwrap(), sequence.len() as i64, "{} at depth {}", code, depth);
+                    assert_eq!(code, simulate(&sequence, &numeric, &directional, depth).unwrap(), "{} at depth {}", code, depth);
+                }
+            }
+        }
+
+        #[test]
+        fn test_custom_layout_sequence() {
+            let gap = KeypadLayout::parse("| 1 | 2 | 3 |\n| 4 |   | A |").unwrap();
+            let directional = KeypadLayout::directional();
+            let keypad = create_numpad_keyboard(&gap, &directional, 0).unwrap();
+            // The gap leaves a single way around in both directions
+            assert_eq!("^<<vA^>>vA", button_sequence("4A", &keypad).unwrap());
+
+            let keypad = create_numpad_keyboard(&gap, &directional, 2).unwrap();
+            let sequence = button_sequence("24A", &keypad).unwrap();
+            assert_eq!("24A", simulate(&sequence, &gap, &directional, 2).unwrap());
+        }
+
+        #[test]
+        fn test_part1_sequences() {
+            let sequences = part1_sequences(BufReader::new(CODES.join("\n").as_bytes())).unwrap();
+            let lengths: Vec<(&str, usize)> = sequences.lines()
+                .map(|line| line.split_once(": ").unwrap())
+                .map(|(code, sequence)| (code, sequence.len()))
+                .collect();
+            assert_eq!(vec![("029A", 68), ("980A", 60), ("179A", 68), ("456A", 64), ("379A", 64)], lengths);
+        }
+
+        #[test]
+        fn test_too_long() {
+            let keypad = create_numpad_keyboard_part2();
+            assert!(button_sequence("029A", &keypad).is_err());
+        }
+    }
+
     //noinspection SpellCheckingInspection
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -315,7 +623,7 @@ mod tests {
 
         fn test_key_cost(expect: i64, code: &str) {
             let numpad = create_numpad_keyboard_part1();
-            assert_eq!(expect, key_cost(code, &numpad));
+            assert_eq!(expect, key_cost(code, &numpad).unwrap());
         }
 
         #[test]
@@ -355,7 +663,7 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(162740, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
@@ -363,6 +671,7 @@ mod tests {
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: i64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -370,7 +679,7 @@ mod tests {
 
         #[test]
         fn part2_final() {
-            assert_eq!(203640915832208, run_on_day_input(day!(), part2).unwrap());
+            check_answer(day!(), 2, part2).unwrap();
         }
     }
 }
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::thread;
 use advent_of_code2024_rust::{day, run_on_day_input};
 use anyhow::*;
 use std::io::{BufRead};
@@ -37,6 +40,115 @@ fn generate_nth_secret(initial: u64, n: usize) -> u64 {
     secret
 }
 
+// Secrets stay below 2^24 from the first step on
+const SECRET_BITS: u32 = 24;
+
+// Inverse of `value ^ (value << shift)` on secrets, as the shifted part is nilpotent
+fn unshift_left(value: u64, shift: u32) -> u64 {
+    let mut result = value;
+    let mut bits = shift;
+    while bits < SECRET_BITS {
+        result ^= value << bits;
+        bits += shift;
+    }
+    prune(result)
+}
+
+fn unshift_right(value: u64, shift: u32) -> u64 {
+    let mut result = value;
+    let mut bits = shift;
+    while bits < SECRET_BITS {
+        result ^= value >> bits;
+        bits += shift;
+    }
+    result
+}
+
+// The secret before `secret`, undoing the steps of `calculate_next_secret` in reverse order
+fn calculate_previous_secret(secret: u64) -> u64 {
+    let secret = unshift_left(prune(secret), 11);
+    let secret = unshift_right(secret, 5);
+    unshift_left(secret, 6)
+}
+
+// `calculate_next_secret` only shifts, xors and drops high bits, so it's a linear map over GF(2)
+// given by the images of the single bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SecretMatrix {
+    columns: [u64; SECRET_BITS as usize],
+}
+
+impl SecretMatrix {
+    fn identity() -> Self {
+        Self { columns: std::array::from_fn(|bit| 1 << bit) }
+    }
+
+    fn next_secret() -> Self {
+        Self { columns: std::array::from_fn(|bit| calculate_next_secret(1 << bit)) }
+    }
+
+    fn apply(&self, secret: u64) -> u64 {
+        (0..SECRET_BITS as usize)
+            .filter(|&bit| secret >> bit & 1 == 1)
+            .fold(0, |result, bit| result ^ self.columns[bit])
+    }
+
+    // `other` followed by `self`
+    fn compose(&self, other: &Self) -> Self {
+        Self { columns: other.columns.map(|column| self.apply(column)) }
+    }
+
+    fn power(&self, mut n: u64) -> Self {
+        let mut result = Self::identity();
+        let mut square = *self;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = square.compose(&result);
+            }
+            square = square.compose(&square);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+// Same as `generate_nth_secret` in O(log n) matrix products
+fn jump_ahead(initial: u64, n: u64) -> u64 {
+    if n == 0 {
+        return initial;
+    }
+    SecretMatrix::next_secret().power(n).apply(prune(initial))
+}
+
+const BABY_STEPS: u64 = 1 << (SECRET_BITS / 2);
+
+// Length of the cycle `seed` runs into, seeds of 2^24 and above aren't on it themselves. The step is
+// a bijection on secrets, so every secret is on a cycle, found by baby-step giant-step in about
+// 2 * 2^12 steps instead of up to 2^24
+fn cycle_length(seed: u64) -> u64 {
+    let start = prune(seed);
+    let mut baby_steps: HashMap<u64, u64> = HashMap::with_capacity(BABY_STEPS as usize);
+    let mut secret = start;
+    for i in 0..BABY_STEPS {
+        if i > 0 && secret == start {
+            return i;
+        }
+        baby_steps.insert(secret, i);
+        secret = calculate_next_secret(secret);
+    }
+
+    // The first giant step landing on a baby step is the first return to `start`
+    let giant_step = SecretMatrix::next_secret().power(BABY_STEPS);
+    let mut secret = start;
+    for j in 1..=BABY_STEPS {
+        secret = giant_step.apply(secret);
+        if let Some(i) = baby_steps.get(&secret) {
+            return j * BABY_STEPS - i;
+        }
+    }
+    unreachable!("Every secret is on a cycle of at most 2^{} steps", SECRET_BITS)
+}
+
 fn parse_input<R: BufRead>(reader: R) -> Vec<u64> {
     reader.lines()
         .flatten()
@@ -49,60 +161,134 @@ fn part1<R: BufRead>(reader: R) -> Result<u64> {
     Ok(parse_input(reader).iter().map(|initial| generate_nth_secret(*initial, 2000)).sum())
 }
 
-fn process_buyer(initial_secret: u64, n: usize, sequence_prices: &mut HashMap<Vec<i32>, u64>) {
-    let size = 4;
+// Price changes are in -9..=9, a sequence of four changes is read as a base-19 number
+const CHANGE_VALUES: usize = 19;
+const SEQUENCE_LENGTH: usize = 4;
+const SEQUENCE_COUNT: usize = CHANGE_VALUES.pow(SEQUENCE_LENGTH as u32);
+
+fn sequence_changes(index: usize) -> [i32; SEQUENCE_LENGTH] {
+    let mut changes = [0; SEQUENCE_LENGTH];
+    let mut rest = index;
+    for change in changes.iter_mut().rev() {
+        *change = (rest % CHANGE_VALUES) as i32 - 9;
+        rest /= CHANGE_VALUES;
+    }
+    changes
+}
 
-    let mut secret = initial_secret;
-    let mut seen_sequences = HashSet::new();
-    let mut changes = VecDeque::with_capacity(size);
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BestSequence {
+    changes: [i32; SEQUENCE_LENGTH],
+    price: u64,
+}
 
+impl Display for BestSequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let changes: Vec<String> = self.changes.iter().map(|change| change.to_string()).collect();
+        write!(f, "{} sells for {}", changes.join(","), self.price)
+    }
+}
+
+// Adds the price at the first occurrence of each change sequence to `totals`, `seen` holds the
+// number of the last buyer having the sequence so it doesn't need clearing between buyers
+fn process_buyer(buyer: usize, initial_secret: u64, n: usize, totals: &mut [u64], seen: &mut [usize]) {
+    let mut secret = initial_secret;
     let mut prev_price = secret % 10;
+    let mut index = 0;
 
     for i in 1..=n {
         secret = calculate_next_secret(secret);
         let current_price = secret % 10;
-        if i > size {
-            changes.pop_front();
-        }
-        changes.push_back(current_price as i32 - prev_price as i32);
+        index = (index * CHANGE_VALUES + (current_price + 9 - prev_price) as usize) % SEQUENCE_COUNT;
         prev_price = current_price;
 
-        if changes.len() < size {
+        if i < SEQUENCE_LENGTH {
             continue;
         }
 
-        let sequence: Vec<i32> = changes.iter().copied().collect();
-        if seen_sequences.insert(sequence.clone()) {
-            sequence_prices.entry(sequence)
-                .and_modify(|total| *total += current_price)
-                .or_insert(current_price);
+        if seen[index] != buyer + 1 {
+            seen[index] = buyer + 1;
+            totals[index] += current_price;
+        }
+    }
+}
+
+// Bananas for each change sequence, indexed as in `sequence_changes`. The buyers are split between
+// `threads` threads with their own totals, which are added up at the end
+fn sequence_totals(initial_secrets: &[u64], n: usize, threads: usize) -> Vec<u64> {
+    let chunk_size = initial_secrets.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = initial_secrets.chunks(chunk_size)
+            .map(|buyers| scope.spawn(move || {
+                let mut totals = vec![0; SEQUENCE_COUNT];
+                let mut seen = vec![0; SEQUENCE_COUNT];
+                for (buyer, &secret) in buyers.iter().enumerate() {
+                    process_buyer(buyer, secret, n, &mut totals, &mut seen);
+                }
+                totals
+            }))
+            .collect();
+
+        let mut totals = vec![0; SEQUENCE_COUNT];
+        for worker in workers {
+            for (total, thread_total) in totals.iter_mut().zip(worker.join().unwrap()) {
+                *total += thread_total;
+            }
         }
+        totals
+    })
+}
+
+// The sequence bringing the most bananas, the smallest index wins a tie
+fn best_sequence(initial_secrets: &[u64], n: usize, threads: usize) -> Option<BestSequence> {
+    let totals = sequence_totals(initial_secrets, n, threads);
+    let (index, &price) = totals.iter()
+        .enumerate()
+        .max_by_key(|&(index, price)| (price, Reverse(index)))?;
+    if price == 0 {
+        return None;
     }
+    Some(BestSequence { changes: sequence_changes(index), price })
+}
+
+fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
 }
 
 //noinspection DuplicatedCode
 fn part2<R: BufRead>(reader: R) -> Result<u64> {
     let initial_secrets = parse_input(reader);
-    let mut sequence_prices: HashMap<Vec<i32>, u64> = HashMap::new();
-    for &secret in &initial_secrets {
-        process_buyer(secret, 2000, &mut sequence_prices);
-    }
+    let best = best_sequence(&initial_secrets, 2000, available_threads());
+    Ok(best.map_or(0, |best| best.price))
+}
+
+// Like part 2, but reports which change sequence the monkey should wait for
+fn best_sale<R: BufRead>(reader: R) -> Result<BestSequence> {
+    let initial_secrets = parse_input(reader);
+    best_sequence(&initial_secrets, 2000, available_threads())
+        .ok_or_else(|| anyhow!("No change sequence sells any bananas"))
+}
 
-    Ok(*sequence_prices
-        .iter()
-        .max_by_key(|&(_, price)| price)
-        .unwrap().1)
+// Where the first buyer's secret sits in the generator's sequence
+fn part1_generator<R: BufRead>(reader: R) -> Result<String> {
+    let first = *parse_input(reader).first().ok_or_else(|| anyhow!("No buyers"))?;
+    Ok(format!(
+        "secret {} follows {}, its 2000th successor is {} and the sequence repeats after {} steps",
+        first, calculate_previous_secret(first), jump_ahead(first, 2000), cycle_length(first)
+    ))
 }
 
 //#region
 
 fn part1_result() -> Result<()> {
     run_on_day_input(day!(), part1)?;
+    run_on_day_input(day!(), part1_generator)?;
     Ok(())
 }
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), best_sale)?;
     Ok(())
 }
 
@@ -175,10 +361,61 @@ mod tests {
         }
     }
 
+    #[cfg(test)]
+    mod prng_tests {
+        use super::*;
+
+        #[test]
+        fn test_previous_secret() {
+            assert_eq!(123, calculate_previous_secret(15887950));
+            assert_eq!(15887950, calculate_previous_secret(16495136));
+            let mut secret = 1;
+            for _ in 0..1000 {
+                secret = calculate_next_secret(secret);
+                assert_eq!(secret, calculate_previous_secret(calculate_next_secret(secret)));
+                assert_eq!(secret, calculate_next_secret(calculate_previous_secret(secret)));
+            }
+        }
+
+        #[test]
+        fn test_jump_ahead() {
+            assert_eq!(8685429, jump_ahead(1, 2000));
+            assert_eq!(8667524, jump_ahead(2024, 2000));
+            assert_eq!(generate_nth_secret(123, 1), jump_ahead(123, 1));
+            assert_eq!(1 << 30, jump_ahead(1 << 30, 0));
+            let matrix = SecretMatrix::next_secret();
+            assert_eq!(matrix.power(7), matrix.power(3).compose(&matrix.power(4)));
+            assert_eq!(SecretMatrix::identity(), matrix.power(0));
+        }
+
+        #[test]
+        fn test_part1_generator() {
+            assert_eq!(
+                format!(
+                    "secret 15887950 follows 123, its 2000th successor is {} and the sequence repeats after {} steps",
+                    generate_nth_secret(15887950, 2000), (1 << 24) - 1
+                ),
+                part1_generator(BufReader::new("15887950\n1\n".as_bytes())).unwrap()
+            );
+            assert_eq!("No buyers", part1_generator(BufReader::new("".as_bytes())).unwrap_err().to_string());
+        }
+
+        #[test]
+        fn test_cycle_length() {
+            assert_eq!(1, cycle_length(0));
+            let period = cycle_length(123);
+            assert_eq!(16777215, period);
+            assert_eq!(period, cycle_length(15887950));
+            assert_eq!(123, jump_ahead(123, period));
+            assert_eq!(jump_ahead(10, 5000), jump_ahead(10, 5000 + 3 * period));
+        }
+    }
+
     //noinspection SpellCheckingInspection
     #[cfg(test)]
     mod part2_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part2(expect: u64, input: &str) {
             assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
@@ -198,10 +435,46 @@ mod tests {
         }
 
         #[test]
-        fn part2_final() {
-            fn part2_final() {
-                assert_eq!(2272, run_on_day_input(day!(), part2).unwrap());
+        fn test_best_sequence() {
+            let best = best_sequence(&[1, 2, 3, 2024], 2000, 2).unwrap();
+            assert_eq!([-2, 1, -1, 3], best.changes);
+            assert_eq!("-2,1,-1,3 sells for 23", best.to_string());
+            assert_eq!(None, best_sequence(&[123], 3, 1));
+        }
+
+        #[test]
+        fn test_best_sale() {
+            let best = best_sale(BufReader::new("1\n2\n3\n2024\n".as_bytes())).unwrap();
+            assert_eq!("-2,1,-1,3 sells for 23", best.to_string());
+            assert_eq!(
+                "No change sequence sells any bananas",
+                best_sale(BufReader::new("".as_bytes())).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn test_threads_agree() {
+            let secrets: Vec<u64> = (1..=37).map(|i| i * 7919).collect();
+            let single = sequence_totals(&secrets, 2000, 1);
+            for threads in [2, 5, 64] {
+                assert_eq!(single, sequence_totals(&secrets, 2000, threads), "{} threads", threads);
             }
         }
+
+        #[test]
+        fn test_first_sale_only() {
+            // 123 has the changes -3,6,-1,-1,0,2,-2,0,-2 over its next nine prices
+            let totals = sequence_totals(&[123], 9, 1);
+            let index = |changes: [i32; 4]| changes.iter().fold(0, |index, change| index * CHANGE_VALUES + (change + 9) as usize);
+            assert_eq!(4, totals[index([-3, 6, -1, -1])]);
+            assert_eq!(6, totals[index([-1, -1, 0, 2])]);
+            assert_eq!(6, totals.iter().filter(|&&total| total > 0).count());
+            assert_eq!(sequence_changes(index([-1, -1, 0, 2])), [-1, -1, 0, 2]);
+        }
+
+        #[test]
+        fn part2_final() {
+            check_answer(day!(), 2, part2).unwrap();
+        }
     }
 }
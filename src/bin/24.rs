@@ -1,6 +1,6 @@
 use advent_of_code2024_rust::{day, run_on_day_input};
 use anyhow::*;
-use std::io::{BufRead};
+use std::io::BufRead;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use itertools::Itertools;
@@ -11,6 +11,34 @@ enum Gate {
     And,
     Or,
     Xor,
+    Nand,
+    Nor,
+    Xnor,
+    Not,
+    Constant(bool),
+}
+
+impl Gate {
+    fn arity(&self) -> usize {
+        match self {
+            Gate::Constant(_) => 0,
+            Gate::Not => 1,
+            _ => 2,
+        }
+    }
+
+    fn apply(&self, inputs: &[bool]) -> bool {
+        match self {
+            Gate::And => inputs[0] && inputs[1],
+            Gate::Or => inputs[0] || inputs[1],
+            Gate::Xor => inputs[0] != inputs[1],
+            Gate::Nand => !(inputs[0] && inputs[1]),
+            Gate::Nor => !(inputs[0] || inputs[1]),
+            Gate::Xnor => inputs[0] == inputs[1],
+            Gate::Not => !inputs[0],
+            Gate::Constant(value) => *value,
+        }
+    }
 }
 
 impl Display for Gate {
@@ -19,6 +47,11 @@ impl Display for Gate {
             Gate::And => write!(f, "AND"),
             Gate::Or => write!(f, "OR"),
             Gate::Xor => write!(f, "XOR"),
+            Gate::Nand => write!(f, "NAND"),
+            Gate::Nor => write!(f, "NOR"),
+            Gate::Xnor => write!(f, "XNOR"),
+            Gate::Not => write!(f, "NOT"),
+            Gate::Constant(value) => write!(f, "{}", if *value { 1 } else { 0 }),
         }
     }
 }
@@ -26,18 +59,28 @@ impl Display for Gate {
 #[derive(Debug, Clone)]
 struct Connection {
     gate: Gate,
-    input1: String,
-    input2: String,
+    inputs: Vec<String>,
     output: String,
 }
 
+impl Connection {
+    // Connection in the puzzle's netlist syntax, e.g. "x00 AND y00 -> z00"
+    fn to_netlist(&self) -> String {
+        match self.inputs.as_slice() {
+            [input1, input2] => format!("{} {} {} -> {}", input1, self.gate, input2, self.output),
+            [input] => format!("{} {} -> {}", self.gate, input, self.output),
+            _ => format!("{} -> {}", self.gate, self.output),
+        }
+    }
+}
+
 impl Display for Connection {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} <- {} {} {} ",
-            self.output, self.input1, self.gate, self.input2
-        )
+        match self.inputs.as_slice() {
+            [input1, input2] => write!(f, "{} <- {} {} {} ", self.output, input1, self.gate, input2),
+            [input] => write!(f, "{} <- {} {} ", self.output, self.gate, input),
+            _ => write!(f, "{} <- {} ", self.output, self.gate),
+        }
     }
 }
 
@@ -47,9 +90,40 @@ struct Circuit {
     connections: Vec<Connection>,
 }
 
+// The circuit in the puzzle's netlist syntax, initial values sorted by wire
+impl Display for Circuit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (wire, value) in self.initial_values.iter().sorted() {
+            writeln!(f, "{}: {}", wire, if *value { 1 } else { 0 })?;
+        }
+        writeln!(f)?;
+        for connection in self.connections.iter() {
+            writeln!(f, "{}", connection.to_netlist())?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+fn parse_gate(name: &str) -> Option<Gate> {
+    match name {
+        "AND" => Some(Gate::And),
+        "OR" => Some(Gate::Or),
+        "XOR" => Some(Gate::Xor),
+        "NAND" => Some(Gate::Nand),
+        "NOR" => Some(Gate::Nor),
+        "XNOR" => Some(Gate::Xnor),
+        "NOT" => Some(Gate::Not),
+        "0" => Some(Gate::Constant(false)),
+        "1" => Some(Gate::Constant(true)),
+        _ => None,
+    }
+}
+
 fn parse_input<R: BufRead>(reader: R) -> Result<Circuit> {
     let initial_value_re = Regex::new(r"^([a-z0-9]+):\s*(\d+)$")?;
-    let connection_re = Regex::new(r"^([a-z0-9]+)\s+(AND|OR|XOR)\s+([a-z0-9]+)\s+->\s+([a-z0-9]+)$")?;
+    let binary_re = Regex::new(r"^([a-z0-9]+)\s+(AND|OR|XOR|NAND|NOR|XNOR)\s+([a-z0-9]+)\s+->\s+([a-z0-9]+)$")?;
+    let unary_re = Regex::new(r"^(NOT)\s+([a-z0-9]+)\s+->\s+([a-z0-9]+)$")?;
+    let constant_re = Regex::new(r"^([01])\s+->\s+([a-z0-9]+)$")?;
 
     let mut initial_values = HashMap::new();
     let mut connections = Vec::new();
@@ -69,24 +143,25 @@ fn parse_input<R: BufRead>(reader: R) -> Result<Circuit> {
                 initial_values.insert(wire, value);
             }
         } else {
-            if let Some(captures) = connection_re.captures(&line) {
-                let input1 = captures[1].to_string();
-                let gate = match &captures[2] {
-                    "AND" => Gate::And,
-                    "OR" => Gate::Or,
-                    "XOR" => Gate::Xor,
-                    _ => continue,
-                };
-                let input2 = captures[3].to_string();
-                let output = captures[4].to_string();
-
-                connections.push(Connection {
-                    gate,
-                    input1,
-                    input2,
-                    output,
-                });
-            }
+            let (gate, inputs, output) = if let Some(captures) = binary_re.captures(&line) {
+                (captures.get(2).unwrap().as_str(), vec![captures[1].to_string(), captures[3].to_string()], captures[4].to_string())
+            } else if let Some(captures) = unary_re.captures(&line) {
+                (captures.get(1).unwrap().as_str(), vec![captures[2].to_string()], captures[3].to_string())
+            } else if let Some(captures) = constant_re.captures(&line) {
+                (captures.get(1).unwrap().as_str(), vec![], captures[2].to_string())
+            } else {
+                continue;
+            };
+
+            let Some(gate) = parse_gate(gate) else {
+                continue;
+            };
+
+            connections.push(Connection {
+                gate,
+                inputs,
+                output,
+            });
         }
     }
 
@@ -118,8 +193,9 @@ fn generate_dependency_map(connections: &Vec<Connection>) -> Result<HashMap<Stri
             Some(connection) => {
                 let mut dependencies = HashSet::new();
                 dependencies.insert(connection.output.clone());
-                dependencies.extend(trace_dependencies(&connection.input1, connection_map, visited));
-                dependencies.extend(trace_dependencies(&connection.input2, connection_map, visited));
+                for input in connection.inputs.iter() {
+                    dependencies.extend(trace_dependencies(input, connection_map, visited));
+                }
 
                 dependencies
             }
@@ -139,65 +215,205 @@ fn generate_dependency_map(connections: &Vec<Connection>) -> Result<HashMap<Stri
 }
 
 
-fn simulate_circuit_recursive(circuit: &Circuit, i: usize) -> Result<(i64, HashMap<String, bool>)> {
-    let mut wire_values = circuit.initial_values.clone();
+#[derive(Debug, Clone)]
+struct CompiledGate {
+    gate: Gate,
+    inputs: Vec<usize>,
+    output: usize,
+}
 
-    let connection_map: HashMap<String, &Connection> = circuit.connections.iter()
-        .map(|conn| (conn.output.clone(), conn))
-        .collect();
+struct CompiledCircuit {
+    wires: Vec<String>,
+    wire_indexes: HashMap<String, usize>,
+    // Index in `gates` of the gate driving each wire
+    producers: Vec<Option<usize>>,
+    // Gates over wire indexes in topological order
+    gates: Vec<CompiledGate>,
+    z_wires: Vec<usize>,
+}
 
-    // Recursive function to compute the value of a wire
-    fn resolve_wire(
-        wire: &str,
-        wire_values: &mut HashMap<String, bool>,
-        connection_map: &HashMap<String, &Connection>,
-        visited: &mut HashSet<String>,
-    ) -> Result<bool> {
-        // If the value of the wire is already known, return it
-        if let Some(&value) = wire_values.get(wire) {
-            return Ok(value);
+impl CompiledCircuit {
+    fn compile(connections: &[Connection]) -> Result<CompiledCircuit> {
+        let mut wires: Vec<String> = Vec::new();
+        let mut wire_indexes: HashMap<String, usize> = HashMap::new();
+        let mut index_of = |wire: &str| -> usize {
+            if let Some(&index) = wire_indexes.get(wire) {
+                return index;
+            }
+            wires.push(wire.to_string());
+            wire_indexes.insert(wire.to_string(), wires.len() - 1);
+            wires.len() - 1
+        };
+
+        let mut indexed: Vec<CompiledGate> = Vec::with_capacity(connections.len());
+        for conn in connections.iter() {
+            if conn.inputs.len() != conn.gate.arity() {
+                bail!("Gate {} driving \"{}\" expects {} inputs", conn.gate, conn.output, conn.gate.arity());
+            }
+            indexed.push(CompiledGate {
+                gate: conn.gate,
+                inputs: conn.inputs.iter().map(|input| index_of(input)).collect(),
+                output: index_of(&conn.output),
+            });
         }
 
-        if visited.contains(wire) {
-            bail!("Recursion in {}", wire);
+        let mut producers: Vec<Option<usize>> = vec![None; wires.len()];
+        for (i, gate) in indexed.iter().enumerate() {
+            if producers[gate.output].is_some() {
+                bail!("Wire \"{}\" is driven by more than one gate", wires[gate.output]);
+            }
+            producers[gate.output] = Some(i);
         }
 
-        visited.insert(wire.to_string());
+        // Kahn's algorithm over gates: a gate is ready when all gates producing its inputs are ready
+        let mut consumers: Vec<Vec<usize>> = vec![Vec::new(); wires.len()];
+        let mut pending_inputs: Vec<usize> = vec![0; indexed.len()];
+        for (i, gate) in indexed.iter().enumerate() {
+            for &input in gate.inputs.iter() {
+                if producers[input].is_some() {
+                    consumers[input].push(i);
+                    pending_inputs[i] += 1;
+                }
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..indexed.len()).filter(|&i| pending_inputs[i] == 0).collect();
+        let mut order: Vec<usize> = Vec::with_capacity(indexed.len());
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for &consumer in consumers[indexed[i].output].iter() {
+                pending_inputs[consumer] -= 1;
+                if pending_inputs[consumer] == 0 {
+                    ready.push(consumer);
+                }
+            }
+        }
+
+        if order.len() != indexed.len() {
+            let cycle = find_cycle(&indexed, &producers, &pending_inputs);
+            bail!("Cycle in circuit: {}", cycle.iter().map(|&w| wires[w].as_str()).join(" -> "));
+        }
+
+        let gates: Vec<CompiledGate> = order.iter().map(|&i| indexed[i].clone()).collect();
+        let mut producers: Vec<Option<usize>> = vec![None; wires.len()];
+        for (i, gate) in gates.iter().enumerate() {
+            producers[gate.output] = Some(i);
+        }
 
-        // Otherwise, find the connection that produces this wire
-        let connection = connection_map.get(wire);
-        if let Some(connection) = connection {
-            let input1 = resolve_wire(&connection.input1, wire_values, connection_map, visited)?;
-            let input2 = resolve_wire(&connection.input2, wire_values, connection_map, visited)?;
-            let result = match connection.gate {
-                Gate::And => input1 && input2,
-                Gate::Or => input1 || input2,
-                Gate::Xor => input1 != input2,
+        let mut z_wires = Vec::new();
+        while let Some(&index) = wire_indexes.get(&format!("z{:02}", z_wires.len())) {
+            if producers[index].is_none() {
+                break;
+            }
+            z_wires.push(index);
+        }
+
+        Ok(CompiledCircuit { wires, wire_indexes, producers, gates, z_wires })
+    }
+
+    fn evaluate(&self, initial_values: &HashMap<String, bool>) -> Result<(i64, HashMap<String, bool>)> {
+        let mut values: Vec<Option<bool>> = vec![None; self.wires.len()];
+        for (wire, &value) in initial_values.iter() {
+            if let Some(&index) = self.wire_indexes.get(wire) {
+                if self.producers[index].is_some() {
+                    bail!("Wire \"{}\" has an initial value but is driven by a gate", wire);
+                }
+                values[index] = Some(value);
+            }
+        }
+
+        let mut inputs = [false; 2];
+        for gate in self.gates.iter() {
+            let mut known = true;
+            for (slot, &input) in inputs.iter_mut().zip(gate.inputs.iter()) {
+                match values[input] {
+                    Some(value) => *slot = value,
+                    None => known = false,
+                }
+            }
+            values[gate.output] = if known {
+                Some(gate.gate.apply(&inputs[..gate.inputs.len()]))
+            } else {
+                None
             };
-            wire_values.insert(wire.to_string(), result); // Cache the computed value
-            Ok(result)
-        } else {
-            bail!("Wire \"{}\" has no input or initial value!", wire);
         }
+
+        let mut number: i64 = 0;
+        for (i, &z) in self.z_wires.iter().enumerate() {
+            match values[z] {
+                Some(value) => number |= if value { 1 << i } else { 0 },
+                None => bail!("Wire \"{}\" has no input or initial value!", self.wires[self.missing_source(z, &values)]),
+            }
+        }
+
+        let mut wire_values = initial_values.clone();
+        wire_values.extend(values.iter().enumerate()
+            .filter_map(|(i, value)| value.map(|v| (self.wires[i].clone(), v))));
+
+        Ok((number, wire_values))
     }
 
-    let mut number: i64 = 0;
-    for i in 0..=i {
-        let key = format!("z{:02}", i);
-        let mut visited = HashSet::new();
-        if connection_map.contains_key(&key) {
-            let value = resolve_wire(&key, &mut wire_values, &connection_map, &mut visited)?;
-            number |= if value { 1 << i } else { 0 };
-        } else {
-            break;
+    fn evaluate_xy(&self, x: u64, y: u64) -> Result<i64> {
+        let mut initial_values = HashMap::new();
+        for (prefix, number) in [('x', x), ('y', y)] {
+            for bit in 0..64 {
+                let key = format!("{}{:02}", prefix, bit);
+                if self.wire_indexes.contains_key(&key) {
+                    initial_values.insert(key, (number >> bit) & 1 == 1);
+                }
+            }
+        }
+        self.evaluate(&initial_values).map(|(result, _)| result)
+    }
+
+    // Bits where the circuit doesn't add x and y, checked with the bit set on either side and on both
+    fn faulty_adder_bits(&self) -> Vec<usize> {
+        let bits = (0..).take_while(|bit| self.wire_indexes.contains_key(&format!("x{:02}", bit))).count();
+        (0..bits)
+            .filter(|&bit| {
+                let one = 1u64 << bit;
+                [(one, 0), (0, one), (one, one)].iter()
+                    .any(|&(x, y)| self.evaluate_xy(x, y).ok() != Some((x + y) as i64))
+            })
+            .collect()
+    }
+
+    fn missing_source(&self, wire: usize, values: &[Option<bool>]) -> usize {
+        let mut wire = wire;
+        while let Some(producer) = self.producers[wire] {
+            let inputs = &self.gates[producer].inputs;
+            wire = *inputs.iter().find(|&&input| values[input].is_none()).unwrap();
         }
+        wire
     }
+}
 
-    Ok((number, wire_values))
+fn find_cycle(
+    gates: &[CompiledGate],
+    producers: &[Option<usize>],
+    pending_inputs: &[usize],
+) -> Vec<usize> {
+    // Walk backwards through unresolved gates until a wire repeats
+    let mut gate = (0..gates.len()).find(|&i| pending_inputs[i] > 0).unwrap();
+    let mut path: Vec<usize> = Vec::new();
+    loop {
+        let output = gates[gate].output;
+        if let Some(position) = path.iter().position(|&w| w == output) {
+            let mut cycle = path[position..].to_vec();
+            cycle.reverse();
+            cycle.push(cycle[0]);
+            return cycle;
+        }
+        path.push(output);
+        gate = gates[gate].inputs.iter()
+            .filter_map(|&input| producers[input])
+            .find(|&producer| pending_inputs[producer] > 0)
+            .unwrap();
+    }
 }
 
 fn simulate_circuit(circuit: &Circuit) -> Result<(i64, HashMap<String, bool>)> {
-    simulate_circuit_recursive(circuit, 64)
+    CompiledCircuit::compile(&circuit.connections)?.evaluate(&circuit.initial_values)
 }
 
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
@@ -209,6 +425,16 @@ fn part2<R: BufRead>(_reader: R) -> Result<i64> {
     Ok(0)
 }
 
+fn adder_report<R: BufRead>(reader: R) -> Result<String> {
+    let circuit = parse_input(reader)?;
+    let bits = CompiledCircuit::compile(&circuit.connections)?.faulty_adder_bits();
+    Ok(if bits.is_empty() {
+        "the adder works for every bit".to_string()
+    } else {
+        format!("the adder fails at bits {}", bits.iter().join(", "))
+    })
+}
+
 //#region
 
 fn part1_result() -> Result<()> {
@@ -218,6 +444,7 @@ fn part1_result() -> Result<()> {
 
 fn part2_result() -> Result<()> {
     run_on_day_input(day!(), part2)?;
+    run_on_day_input(day!(), adder_report)?;
     Ok(())
 }
 
@@ -237,6 +464,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -319,7 +547,142 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(51657025112326, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
+        }
+    }
+
+    #[cfg(test)]
+    mod compiled_circuit_tests {
+        use super::*;
+
+        fn compile(input: &str) -> Result<CompiledCircuit> {
+            CompiledCircuit::compile(&parse_input(BufReader::new(input.as_bytes()))?.connections)
+        }
+
+        #[test]
+        fn test_rerun_with_inputs() {
+            let circuit = compile(indoc! {"
+                x00: 0
+
+                x00 XOR y00 -> z00
+                x00 AND y00 -> c00
+                x01 XOR y01 -> s01
+                s01 XOR c00 -> z01
+                x01 AND y01 -> a01
+                s01 AND c00 -> b01
+                a01 OR b01 -> z02
+            "}).unwrap();
+
+            for x in 0..4 {
+                for y in 0..4 {
+                    assert_eq!((x + y) as i64, circuit.evaluate_xy(x, y).unwrap());
+                }
+            }
+        }
+
+        #[test]
+        fn test_adder_report() {
+            let report = |input: &str| adder_report(BufReader::new(input.as_bytes())).unwrap();
+            let adder = indoc! {"
+                x00: 0
+
+                x00 XOR y00 -> z00
+                x00 AND y00 -> c00
+                x01 XOR y01 -> s01
+                s01 XOR c00 -> z01
+                x01 AND y01 -> a01
+                s01 AND c00 -> b01
+                a01 OR b01 -> z02
+            "};
+            assert_eq!("the adder works for every bit", report(adder));
+            assert_eq!("the adder fails at bits 1", report(&adder.replace("x01 XOR y01 -> s01", "x01 AND y01 -> s01")));
+        }
+
+        #[test]
+        fn test_cycle() {
+            let error = compile(indoc! {"
+                x00: 1
+
+                x00 AND bbb -> aaa
+                aaa OR x00 -> ccc
+                ccc XOR x00 -> bbb
+                bbb AND x00 -> z00
+            "}).err().unwrap();
+
+            assert_eq!("Cycle in circuit: ccc -> bbb -> aaa -> ccc", error.to_string());
+        }
+
+        #[test]
+        fn test_missing_input() {
+            let circuit = compile(indoc! {"
+                x00: 1
+
+                x00 AND y00 -> z00
+            "}).unwrap();
+
+            let error = circuit.evaluate(&HashMap::from([("x00".to_string(), true)])).err().unwrap();
+            assert_eq!("Wire \"y00\" has no input or initial value!", error.to_string());
+        }
+
+        #[test]
+        fn test_driven_initial_value() {
+            let circuit = parse_input(BufReader::new(indoc! {"
+                x00: 1
+                y00: 1
+                z00: 0
+
+                x00 AND y00 -> z00
+            "}.as_bytes())).unwrap();
+
+            let error = simulate_circuit(&circuit).err().unwrap();
+            assert_eq!("Wire \"z00\" has an initial value but is driven by a gate", error.to_string());
+        }
+    }
+
+    #[cfg(test)]
+    mod netlist_tests {
+        use super::*;
+
+        // Two-bit subtractor x - y built from the extended gate set, z02 is the borrow
+        const SUBTRACTOR: &str = indoc! {"
+            x00: 0
+            x01: 0
+            y00: 0
+            y01: 0
+
+            x00 XOR y00 -> z00
+            NOT x00 -> nx0
+            nx0 AND y00 -> b00
+            x01 XNOR y01 -> e01
+            e01 XNOR b00 -> z01
+            NOT y01 -> ny1
+            x01 NOR ny1 -> w01
+            e01 NAND b00 -> p01
+            NOT p01 -> q01
+            1 -> one
+            q01 AND one -> r01
+            0 -> zero
+            w01 OR zero -> s01
+            s01 OR r01 -> z02
+        "};
+
+        #[test]
+        fn test_subtractor() {
+            let circuit = parse_input(BufReader::new(SUBTRACTOR.as_bytes())).unwrap();
+            let compiled = CompiledCircuit::compile(&circuit.connections).unwrap();
+
+            for x in 0..4u64 {
+                for y in 0..4u64 {
+                    let expected = (x.wrapping_sub(y) & 0b11) | if x < y { 0b100 } else { 0 };
+                    assert_eq!(expected as i64, compiled.evaluate_xy(x, y).unwrap(), "{} - {}", x, y);
+                }
+            }
+        }
+
+        #[test]
+        fn test_netlist() {
+            let circuit = parse_input(BufReader::new(SUBTRACTOR.as_bytes())).unwrap();
+            assert_eq!(SUBTRACTOR, circuit.to_string());
         }
     }
 
@@ -360,26 +723,12 @@ mod tests {
             ((x + y, result as u64), wire_values)
         }
 
-        fn test_run(x: u64, y: u64, connections: &Vec<Connection>) -> Result<u64> {
-            let x_map = binary_to_map(x, 'x');
-            let y_map = binary_to_map(y, 'y');
-
-            let mut initial_values = HashMap::new();
-            initial_values.extend(x_map);
-            initial_values.extend(y_map);
-
-            let connections = connections.clone();
-
-            let (result, _) = simulate_circuit(&Circuit {
-                initial_values,
-                connections
-            })?;
-
-            Ok(result as u64)
+        fn test_run(x: u64, y: u64, circuit: &CompiledCircuit) -> Result<u64> {
+            circuit.evaluate_xy(x, y).map(|result| result as u64)
         }
 
-        fn test_run_full(x: u64, y: u64, connections: &Vec<Connection>) -> bool {
-            match test_run(x, y, connections) {
+        fn test_run_full(x: u64, y: u64, circuit: &CompiledCircuit) -> bool {
+            match test_run(x, y, circuit) {
                 Ok(result) => result == x + y,
                 Err(_) => {
                     false
@@ -387,8 +736,8 @@ mod tests {
             }
         }
 
-        fn test_bit_part(expect: u64, i: usize, x: u64, y: u64, connections: &Vec<Connection>) -> bool {
-            match test_run(x, y, connections) {
+        fn test_bit_part(expect: u64, i: usize, x: u64, y: u64, circuit: &CompiledCircuit) -> bool {
+            match test_run(x, y, circuit) {
                 Ok(result) => {
                     if result >> i & 1 != expect {
                         return false;
@@ -402,14 +751,14 @@ mod tests {
             true
         }
 
-        fn test_bit_heuristic(i: usize, connections: &Vec<Connection>) -> bool {
-            test_bit_part(1, i, 1 << i, 0, connections) &&
-            test_bit_part(1, i, 0, 1 << i, connections) &&
-            test_bit_part(0, i, 1 << i, 1 << i, connections) &&
+        fn test_bit_heuristic(i: usize, circuit: &CompiledCircuit) -> bool {
+            test_bit_part(1, i, 1 << i, 0, circuit) &&
+            test_bit_part(1, i, 0, 1 << i, circuit) &&
+            test_bit_part(0, i, 1 << i, 1 << i, circuit) &&
             (i == 0 ||
-                (test_bit_part(1, i, 11 << (i - 1), 11 << (i - 1), connections)) &&
-                (test_bit_part(0, i, (1 << i) - 1, (1 << (i + 1)) - 1, connections)) &&
-                (test_bit_part(0, i, (1 << (i + 1)) - 1, (1 << i) - 1, connections))
+                (test_bit_part(1, i, 11 << (i - 1), 11 << (i - 1), circuit)) &&
+                (test_bit_part(0, i, (1 << i) - 1, (1 << (i + 1)) - 1, circuit)) &&
+                (test_bit_part(0, i, (1 << (i + 1)) - 1, (1 << i) - 1, circuit))
             )
         }
 
@@ -423,8 +772,9 @@ mod tests {
                     let indent = "  ".repeat(level);
                     let value = wire_values.get(&key).map(|v| if *v { 1 } else { 0 }).unwrap_or(-1);
                     println!("{indent}{gate}({})", value);
-                    queue.push_front((gate.input2.clone(), level + 1));
-                    queue.push_front((gate.input1.clone(), level + 1));
+                    for input in gate.inputs.iter().rev() {
+                        queue.push_front((input.clone(), level + 1));
+                    }
                 }
             }
         }
@@ -439,38 +789,38 @@ mod tests {
 
         #[test]
         fn test_bit_0() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(test_bit_heuristic(0, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(test_bit_heuristic(0, &circuit));
         }
 
         #[test]
         fn test_bit_1() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(test_bit_heuristic(1, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(test_bit_heuristic(1, &circuit));
         }
 
         #[test]
         fn test_bit_2() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(test_bit_heuristic(2, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(test_bit_heuristic(2, &circuit));
         }
 
         #[test]
         fn test_bit_3() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(test_bit_heuristic(3, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(test_bit_heuristic(3, &circuit));
         }
 
         #[test]
         fn test_bit_4() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(test_bit_heuristic(4, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(test_bit_heuristic(4, &circuit));
         }
 
         #[test]
         fn test_bit_5() {
-            let connections = parse_input(day_input(day!())).unwrap().connections;
-            assert!(!test_bit_heuristic(5, &connections));
+            let circuit = CompiledCircuit::compile(&parse_input(day_input(day!())).unwrap().connections).unwrap();
+            assert!(!test_bit_heuristic(5, &circuit));
         }
 
         #[test]
@@ -541,7 +891,10 @@ mod tests {
         }
 
         fn test_connections(i: usize, connection: &Vec<Connection>) -> bool {
-            test_run_full(0, 0, connection) && test_bit_heuristic(i, connection)
+            match CompiledCircuit::compile(connection) {
+                Ok(circuit) => test_run_full(0, 0, &circuit) && test_bit_heuristic(i, &circuit),
+                Err(_) => false,
+            }
         }
 
         fn auto_fix(i: u32, connection: &Vec<Connection>) -> (String, String) {
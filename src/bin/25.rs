@@ -1,6 +1,9 @@
 use advent_of_code2024_rust::{day, run_on_day_input};
+use advent_of_code2024_rust::answers::ANSWERS;
 use anyhow::*;
 use std::io::{BufRead};
+use std::fmt::{Display, Formatter};
+use itertools::Itertools;
 
 #[derive(Debug, PartialEq)]
 struct Lock {
@@ -70,27 +73,113 @@ fn parse_key(grid: &[String]) -> Option<Key> {
 }
 
 
+const MAX_HEIGHT: usize = 5;
+
+#[derive(Debug, PartialEq)]
+struct ColumnOverlap {
+    column: usize,
+    overlap: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Fit {
+    lock: usize,
+    key: usize,
+    overlaps: Vec<ColumnOverlap>,
+}
+
+impl Fit {
+    fn fits(&self) -> bool {
+        self.overlaps.is_empty()
+    }
+}
+
+impl Display for Fit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lock {} / key {}: ", self.lock, self.key)?;
+        if self.fits() {
+            return write!(f, "fit");
+        }
+        let overlaps = self.overlaps.iter()
+            .map(|o| format!("column {} by {}", o.column, o.overlap))
+            .join(", ");
+        write!(f, "overlap in {}", overlaps)
+    }
+}
+
+fn check_fit(lock: &Lock, key: &Key) -> Vec<ColumnOverlap> {
+    lock.heights.iter()
+        .zip(key.heights.iter())
+        .enumerate()
+        .filter(|(_, (&lock_height, &key_height))| lock_height + key_height > MAX_HEIGHT)
+        .map(|(column, (&lock_height, &key_height))| ColumnOverlap {
+            column,
+            overlap: lock_height + key_height - MAX_HEIGHT,
+        })
+        .collect()
+}
+
+fn fit_report(locks: &[Lock], keys: &[Key]) -> Vec<Fit> {
+    locks.iter().enumerate()
+        .flat_map(|(lock_index, lock)| {
+            keys.iter().enumerate().map(move |(key_index, key)| Fit {
+                lock: lock_index,
+                key: key_index,
+                overlaps: check_fit(lock, key),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct CalendarSummary {
+    // (day, part 1 verified, part 2 verified)
+    days: Vec<(u32, bool, bool)>,
+}
+
+impl CalendarSummary {
+    // The last star needs `last_answer`, the last day's part 1 on the given input, to be the
+    // verified one and every other star to be verified
+    fn collect(answers: &[[Option<&str>; 2]], last_answer: &str) -> CalendarSummary {
+        let mut days: Vec<(u32, bool, bool)> = answers.iter().enumerate()
+            .map(|(i, [part1, part2])| (i as u32 + 1, part1.is_some(), part2.is_some()))
+            .collect();
+
+        let (last, previous) = days.split_last_mut().unwrap();
+        last.1 = answers[answers.len() - 1][0] == Some(last_answer);
+        last.2 = last.1 && previous.iter().all(|(_, part1, part2)| *part1 && *part2);
+
+        CalendarSummary { days }
+    }
+
+    fn stars(&self) -> usize {
+        self.days.iter()
+            .map(|(_, part1, part2)| *part1 as usize + *part2 as usize)
+            .sum()
+    }
+}
+
+impl Display for CalendarSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}/{} stars", self.stars(), self.days.len() * 2)?;
+        for (day, part1, part2) in self.days.iter() {
+            let star = |verified: bool| if verified { '*' } else { '.' };
+            writeln!(f, "Day {:02}: {}{}", day, star(*part1), star(*part2))?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
 //noinspection DuplicatedCode
 fn part1<R: BufRead>(reader: R) -> Result<i64> {
     let (locks, keys) = parse_input(reader);
-    Ok(
-        locks.iter()
-            .map(|lock| {
-                keys.iter().filter(|key| {
-                    lock.heights.iter()
-                        .zip(key.heights.iter())
-                        .all(|(lock_height, key_height)| {
-                            lock_height + key_height <= 5
-                        })
-                }).count() as i64
-            })
-            .sum()
-    )
+    Ok(fit_report(&locks, &keys).iter().filter(|fit| fit.fits()).count() as i64)
 }
 
 //noinspection DuplicatedCode
-fn part2<R: BufRead>(_reader: R) -> Result<i64> {
-    Ok(0)
+fn part2<R: BufRead>(reader: R) -> Result<CalendarSummary> {
+    let fitting = part1(reader)?;
+    Ok(CalendarSummary::collect(&ANSWERS, &fitting.to_string()))
 }
 
 //#region
@@ -151,6 +240,7 @@ mod tests {
     #[cfg(test)]
     mod part1_tests {
         use super::*;
+        use advent_of_code2024_rust::answers::check_answer;
 
         fn test_part1(expect: i64, input: &str) {
             assert_eq!(expect, part1(BufReader::new(input.as_bytes())).unwrap());
@@ -206,27 +296,62 @@ mod tests {
 
         #[test]
         fn part1_final() {
-            assert_eq!(3127, run_on_day_input(day!(), part1).unwrap());
+            check_answer(day!(), 1, part1).unwrap();
         }
     }
 
-    //noinspection SpellCheckingInspection
     #[cfg(test)]
-    mod part2_tests {
+    mod fit_report_tests {
         use super::*;
 
-        fn test_part2(expect: i64, input: &str) {
-            assert_eq!(expect, part2(BufReader::new(input.as_bytes())).unwrap());
+        #[test]
+        fn test_fit_report() {
+            let locks = vec![
+                Lock { heights: vec![0, 5, 3, 4, 3] },
+                Lock { heights: vec![1, 2, 0, 5, 3] },
+            ];
+            let keys = vec![
+                Key { heights: vec![5, 0, 2, 1, 3] },
+                Key { heights: vec![3, 0, 2, 0, 1] },
+            ];
+
+            let report = fit_report(&locks, &keys);
+
+            assert_eq!(
+                report.iter().map(|fit| (fit.lock, fit.key, fit.fits())).collect::<Vec<_>>(),
+                vec![(0, 0, false), (0, 1, true), (1, 0, false), (1, 1, true)]
+            );
+            assert_eq!(report[0].overlaps, vec![ColumnOverlap { column: 4, overlap: 1 }]);
+            assert_eq!("lock 0 / key 1: fit", report[1].to_string());
+            assert_eq!(
+                "lock 1 / key 0: overlap in column 0 by 1, column 3 by 1, column 4 by 1",
+                report[2].to_string()
+            );
         }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[cfg(test)]
+    mod part2_tests {
+        use super::*;
 
         #[test]
-        fn test1() {
-            test_part2(
-                0,
-                indoc! {"
-                1   2
-            "}
-            );
+        fn test_summary() {
+            let mut answers = [[Some("1"), Some("2")]; 25];
+            answers[24] = [Some("3"), None];
+            let summary = CalendarSummary::collect(&answers, "3");
+            assert_eq!(50, summary.stars());
+            assert!(summary.to_string().starts_with("50/50 stars\nDay 01: **\n"));
+            assert!(summary.to_string().ends_with("Day 24: **\nDay 25: **\n"));
+
+            // A wrong answer for the last day costs both of its stars
+            assert!(CalendarSummary::collect(&answers, "4").to_string().ends_with("Day 25: ..\n"));
+
+            answers[0] = [Some("1"), None];
+            let summary = CalendarSummary::collect(&answers, "3");
+            assert_eq!(48, summary.stars());
+            assert!(summary.to_string().starts_with("48/50 stars\nDay 01: *.\n"));
+            assert!(summary.to_string().ends_with("Day 25: *.\n"));
         }
 
         #[test]
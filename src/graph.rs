@@ -0,0 +1,94 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hash;
+
+// Nodes on a cycle in edge direction, the first node is repeated at the end
+#[derive(Debug, PartialEq)]
+pub struct Cycle<T>(pub Vec<T>);
+
+impl<T: Display> Display for Cycle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cycle: ")?;
+        for (i, node) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{}", node)?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+impl<T: Display + Debug> std::error::Error for Cycle<T> {}
+
+// Orders nodes so that for every (before, after) edge `before` comes first. When several nodes
+// are ready, the one listed first in `nodes` is taken. Edges with unknown nodes are ignored.
+pub fn topological_sort<T: Eq + Hash + Clone>(nodes: &[T], edges: &[(T, T)]) -> Result<Vec<T>, Cycle<T>> {
+    let indexes: HashMap<&T, usize> = nodes.iter().enumerate().map(|(i, node)| (node, i)).collect();
+
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (before, after) in edges.iter() {
+        if let (Some(&before), Some(&after)) = (indexes.get(before), indexes.get(after)) {
+            successors[before].push(after);
+            predecessors[after].push(before);
+        }
+    }
+
+    let mut pending: Vec<usize> = predecessors.iter().map(|p| p.len()).collect();
+    let mut ready: BTreeSet<usize> = (0..nodes.len()).filter(|&i| pending[i] == 0).collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(node) = ready.pop_first() {
+        order.push(nodes[node].clone());
+        for &next in successors[node].iter() {
+            pending[next] -= 1;
+            if pending[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+
+    if order.len() == nodes.len() {
+        return Ok(order);
+    }
+
+    // Walk back through unsorted predecessors until a node repeats
+    let mut node = (0..nodes.len()).find(|&i| pending[i] > 0).unwrap();
+    let mut path: Vec<usize> = Vec::new();
+    loop {
+        if let Some(position) = path.iter().position(|&n| n == node) {
+            let mut cycle: Vec<usize> = path[position..].iter().rev().copied().collect();
+            // Start from the node listed first to keep the report stable
+            let first = cycle.iter().enumerate().min_by_key(|(_, &n)| n).map(|(i, _)| i).unwrap();
+            cycle.rotate_left(first);
+            cycle.push(cycle[0]);
+            return Err(Cycle(cycle.iter().map(|&n| nodes[n].clone()).collect()));
+        }
+        path.push(node);
+        node = *predecessors[node].iter().find(|&&p| pending[p] > 0).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(Ok(vec![3, 1, 2]), topological_sort(&[1, 2, 3], &[(3, 1), (1, 2)]));
+    }
+
+    #[test]
+    fn test_ambiguous_order_keeps_input_order() {
+        assert_eq!(Ok(vec![5, 1, 4, 2]), topological_sort(&[5, 1, 4, 2], &[(1, 2), (7, 5)]));
+        assert_eq!(Ok(vec![1, 4, 5, 2]), topological_sort(&[5, 1, 4, 2], &[(4, 5), (1, 2)]));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let cycle = topological_sort(&["a", "b", "c", "d"], &[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d")])
+            .unwrap_err();
+        assert_eq!(Cycle(vec!["a", "b", "c", "a"]), cycle);
+        assert_eq!("Cycle: a -> b -> c -> a", cycle.to_string());
+    }
+}
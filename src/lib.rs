@@ -1,4 +1,9 @@
+pub mod answers;
+pub mod graph;
+pub mod linear;
 pub mod matrix;
+pub mod numeric;
+pub mod word_search;
 
 use std::fs::File;
 use std::io::{BufReader};
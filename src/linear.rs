@@ -0,0 +1,322 @@
+use anyhow::*;
+use crate::numeric::Integer;
+
+#[derive(Debug, PartialEq)]
+pub enum Solution {
+    // The only solution, all components are integers
+    Unique(Vec<Integer>),
+    // The only solution has a fractional component
+    Fractional,
+    // Zero determinant, there are no solutions or infinitely many
+    Singular,
+}
+
+// Fraction-free Gaussian elimination (Bareiss), every division is exact
+pub fn determinant(matrix: &[Vec<Integer>]) -> Result<Integer> {
+    let n = matrix.len();
+    if matrix.iter().any(|row| row.len() != n) {
+        bail!("Matrix is not square");
+    }
+
+    let mut m = matrix.to_vec();
+    let mut negate = false;
+    let mut previous = Integer::from(1);
+    for k in 0..n {
+        if m[k][k].is_zero() {
+            let Some(pivot) = (k + 1..n).find(|&i| !m[i][k].is_zero()) else {
+                return Ok(Integer::zero());
+            };
+            m.swap(k, pivot);
+            negate = !negate;
+        }
+        for i in k + 1..n {
+            for j in k + 1..n {
+                let value = m[i][j].mul(&m[k][k])?.sub(&m[i][k].mul(&m[k][j])?)?;
+                m[i][j] = value.div_rem(&previous)?.0;
+            }
+        }
+        previous = m[k][k].clone();
+    }
+
+    if negate { previous.neg() } else { Ok(previous) }
+}
+
+// Solves matrix * x = rhs by Cramer's rule, meant for small systems
+pub fn solve(matrix: &[Vec<Integer>], rhs: &[Integer]) -> Result<Solution> {
+    if rhs.len() != matrix.len() {
+        bail!("Expected {} right-hand side values, got {}", matrix.len(), rhs.len());
+    }
+
+    let det = determinant(matrix)?;
+    if det.is_zero() {
+        return Ok(Solution::Singular);
+    }
+
+    let mut solution = Vec::with_capacity(rhs.len());
+    for column in 0..rhs.len() {
+        let replaced: Vec<Vec<Integer>> = matrix.iter().zip(rhs.iter())
+            .map(|(row, value)| {
+                let mut row = row.clone();
+                row[column] = value.clone();
+                row
+            })
+            .collect();
+        match determinant(&replaced)?.div_exact(&det)? {
+            Some(value) => solution.push(value),
+            None => return Ok(Solution::Fractional),
+        }
+    }
+    Ok(Solution::Unique(solution))
+}
+
+// (g, x, y) with a * x + b * y = g = gcd(a, b) and g >= 0
+pub fn extended_gcd(a: &Integer, b: &Integer) -> Result<(Integer, Integer, Integer)> {
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut x0, mut x1) = (Integer::from(1), Integer::zero());
+    let (mut y0, mut y1) = (Integer::zero(), Integer::from(1));
+    while !r1.is_zero() {
+        let (quotient, remainder) = r0.div_rem(&r1)?;
+        let x2 = x0.sub(&quotient.mul(&x1)?)?;
+        let y2 = y0.sub(&quotient.mul(&y1)?)?;
+        (r0, r1) = (r1, remainder);
+        (x0, x1) = (x1, x2);
+        (y0, y1) = (y1, y2);
+    }
+    if r0.is_negative() {
+        Ok((r0.neg()?, x0.neg()?, y0.neg()?))
+    } else {
+        Ok((r0, x0, y0))
+    }
+}
+
+// Non-negative (n, m) with a * n + b * m = target minimising cost_a * n + cost_b * m,
+// a and b must be non-negative
+pub fn min_cost_combination(
+    a: &Integer,
+    b: &Integer,
+    target: &Integer,
+    cost_a: &Integer,
+    cost_b: &Integer,
+) -> Result<Option<(Integer, Integer)>> {
+    if a.is_negative() || b.is_negative() {
+        bail!("Negative step in {} * n + {} * m", a, b);
+    }
+    // A single usable step
+    let only = |step: &Integer| -> Result<Option<Integer>> {
+        Ok(if step.is_zero() {
+            if target.is_zero() { Some(Integer::zero()) } else { None }
+        } else {
+            target.div_exact(step)?.filter(|count| !count.is_negative())
+        })
+    };
+    if a.is_zero() {
+        return Ok(only(b)?.map(|m| (Integer::zero(), m)));
+    }
+    if b.is_zero() {
+        return Ok(only(a)?.map(|n| (n, Integer::zero())));
+    }
+
+    let (g, x, y) = extended_gcd(a, b)?;
+    let Some(scale) = target.div_exact(&g)? else {
+        return Ok(None);
+    };
+    // All solutions are n = n0 + k * step_n, m = m0 - k * step_m
+    let (n0, m0) = (x.mul(&scale)?, y.mul(&scale)?);
+    let (step_n, step_m) = (b.div_rem(&g)?.0, a.div_rem(&g)?.0);
+
+    let k_min = n0.neg()?.div_ceil(&step_n)?;
+    let k_max = m0.div_floor(&step_m)?;
+    if k_min > k_max {
+        return Ok(None);
+    }
+
+    // Cost changes linearly with k
+    let slope = cost_a.mul(&step_n)?.sub(&cost_b.mul(&step_m)?)?;
+    let k = if slope.is_negative() { k_max } else { k_min };
+    Ok(Some((n0.add(&k.mul(&step_n)?)?, m0.sub(&k.mul(&step_m)?)?)))
+}
+
+// Non-negative counts for 2D vectors that sum up to target with the minimal total cost. Two vectors
+// are solved exactly, counts of any further vectors are enumerated and must stay small.
+pub fn min_cost_combination_2d(
+    vectors: &[[Integer; 2]],
+    costs: &[Integer],
+    target: &[Integer; 2],
+) -> Result<Option<Vec<Integer>>> {
+    if vectors.len() != costs.len() {
+        bail!("Expected {} costs, got {}", vectors.len(), costs.len());
+    }
+    if let Some(vector) = vectors.iter().find(|v| v.iter().any(|c| c.is_negative())) {
+        bail!("Negative vector ({}, {})", vector[0], vector[1]);
+    }
+    if let Some(cost) = costs.iter().find(|cost| cost.is_negative()) {
+        bail!("Negative cost {}", cost);
+    }
+    if target.iter().any(|c| c.is_negative()) {
+        return Ok(None);
+    }
+
+    let Some((last, rest)) = vectors.split_last() else {
+        return Ok(if target.iter().all(|c| c.is_zero()) { Some(Vec::new()) } else { None });
+    };
+    if rest.len() < 2 {
+        return min_cost_pair(vectors, costs, target);
+    }
+
+    // Counts beyond which the last vector overshoots the target
+    let mut limit: Option<Integer> = None;
+    for (step, value) in last.iter().zip(target.iter()) {
+        if !step.is_zero() {
+            let count = value.div_floor(step)?;
+            limit = Some(match limit {
+                Some(limit) if limit < count => limit,
+                _ => count,
+            });
+        }
+    }
+    let limit = limit.unwrap_or_else(Integer::zero).to_i64()?;
+    if limit > ENUMERATION_LIMIT {
+        bail!("Vector ({}, {}) may be used up to {} times, too many to enumerate", last[0], last[1], limit);
+    }
+
+    let mut best: Option<(Integer, Vec<Integer>)> = None;
+    for count in 0..=limit {
+        let count = Integer::from(count);
+        let remaining = [
+            target[0].sub(&last[0].mul(&count)?)?,
+            target[1].sub(&last[1].mul(&count)?)?,
+        ];
+        let Some(mut counts) = min_cost_combination_2d(rest, &costs[..rest.len()], &remaining)? else {
+            continue;
+        };
+        counts.push(count);
+        let cost = total_cost(&counts, costs)?;
+        if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+            best = Some((cost, counts));
+        }
+    }
+    Ok(best.map(|(_, counts)| counts))
+}
+
+const ENUMERATION_LIMIT: i64 = 1_000_000;
+
+fn total_cost(counts: &[Integer], costs: &[Integer]) -> Result<Integer> {
+    counts.iter().zip(costs.iter())
+        .try_fold(Integer::zero(), |acc, (count, cost)| acc.add(&count.mul(cost)?))
+}
+
+// At most two vectors, solved exactly or along their common line when they are collinear
+fn min_cost_pair(vectors: &[[Integer; 2]], costs: &[Integer], target: &[Integer; 2]) -> Result<Option<Vec<Integer>>> {
+    let zero = [Integer::zero(), Integer::zero()];
+    let (a, b) = (&vectors[0], vectors.get(1).unwrap_or(&zero));
+    let (cost_a, cost_b) = (&costs[0], costs.get(1).unwrap_or(&costs[0]));
+
+    let counts = match solve(&[vec![a[0].clone(), b[0].clone()], vec![a[1].clone(), b[1].clone()]], target)? {
+        Solution::Unique(counts) => Some((counts[0].clone(), counts[1].clone())),
+        Solution::Fractional => None,
+        Solution::Singular => {
+            // Counts along one axis fix the other axis too
+            let axis = if a[0].is_zero() && b[0].is_zero() { 1 } else { 0 };
+            min_cost_combination(&a[axis], &b[axis], &target[axis], cost_a, cost_b)?
+        }
+    };
+
+    let Some((n, m)) = counts else {
+        return Ok(None);
+    };
+    if n.is_negative() || m.is_negative() {
+        return Ok(None);
+    }
+    for axis in 0..2 {
+        if n.mul(&a[axis])?.add(&m.mul(&b[axis])?)? != target[axis] {
+            return Ok(None);
+        }
+    }
+    Ok(Some(if vectors.len() == 1 { vec![n] } else { vec![n, m] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[i64]) -> Vec<Integer> {
+        values.iter().map(|&v| Integer::from(v)).collect()
+    }
+
+    fn matrix(rows: &[&[i64]]) -> Vec<Vec<Integer>> {
+        rows.iter().map(|row| integers(row)).collect()
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert_eq!(Integer::from(-2), determinant(&matrix(&[&[1, 2], &[3, 4]])).unwrap());
+        assert_eq!(Integer::from(-2), determinant(&matrix(&[&[0, 1, 2], &[1, 0, 3], &[4, -3, 8]])).unwrap());
+        assert_eq!(Integer::zero(), determinant(&matrix(&[&[1, 2, 3], &[2, 4, 6], &[1, 0, 1]])).unwrap());
+        assert!(determinant(&matrix(&[&[1, 2]])).is_err());
+    }
+
+    #[test]
+    fn test_solve() {
+        let claw = matrix(&[&[94, 22], &[34, 67]]);
+        assert_eq!(Solution::Unique(integers(&[80, 40])), solve(&claw, &integers(&[8400, 5400])).unwrap());
+        assert_eq!(Solution::Fractional, solve(&claw, &integers(&[8401, 5400])).unwrap());
+        assert_eq!(Solution::Singular, solve(&matrix(&[&[1, 2], &[2, 4]]), &integers(&[3, 6])).unwrap());
+
+        let system = matrix(&[&[2, 1, -1], &[-3, -1, 2], &[-2, 1, 2]]);
+        assert_eq!(Solution::Unique(integers(&[2, 3, -1])), solve(&system, &integers(&[8, -11, -3])).unwrap());
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(&Integer::from(240), &Integer::from(46)).unwrap();
+        assert_eq!(Integer::from(2), g);
+        assert_eq!(g, Integer::from(240).mul(&x).unwrap().add(&Integer::from(46).mul(&y).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_min_cost_combination() {
+        let combination = |a: i64, b: i64, target: i64, cost_a: i64, cost_b: i64| {
+            min_cost_combination(
+                &Integer::from(a), &Integer::from(b), &Integer::from(target),
+                &Integer::from(cost_a), &Integer::from(cost_b),
+            ).unwrap().map(|(n, m)| (n.to_i64().unwrap(), m.to_i64().unwrap()))
+        };
+        assert_eq!(Some((1, 1)), combination(3, 5, 8, 3, 1));
+        assert_eq!(Some((0, 2)), combination(1, 2, 4, 3, 1));
+        assert_eq!(Some((2, 1)), combination(6, 1, 13, 3, 1));
+        assert_eq!(Some((2, 1)), combination(2, 3, 7, 1, 1));
+        assert_eq!(None, combination(4, 6, 9, 3, 1));
+        assert_eq!(None, combination(5, 7, 3, 3, 1));
+        assert_eq!(Some((0, 3)), combination(0, 2, 6, 3, 1));
+        assert_eq!(Some((0, 0)), combination(0, 0, 0, 3, 1));
+    }
+
+    #[test]
+    fn test_min_cost_combination_2d() {
+        let combination = |vectors: &[[i64; 2]], costs: &[i64], target: [i64; 2]| {
+            let vectors: Vec<[Integer; 2]> = vectors.iter().map(|v| v.map(Integer::from)).collect();
+            min_cost_combination_2d(&vectors, &integers(costs), &target.map(Integer::from))
+                .unwrap()
+                .map(|counts| counts.iter().map(|c| c.to_i64().unwrap()).collect::<Vec<i64>>())
+        };
+        assert_eq!(Some(vec![80, 40]), combination(&[[94, 34], [22, 67]], &[3, 1], [8400, 5400]));
+        assert_eq!(Some(vec![1, 1]), combination(&[[3, 3], [5, 5]], &[3, 1], [8, 8]));
+        assert_eq!(Some(vec![3]), combination(&[[2, 1]], &[1], [6, 3]));
+        assert_eq!(None, combination(&[[2, 1]], &[1], [6, 4]));
+        assert_eq!(Some(vec![]), combination(&[], &[], [0, 0]));
+        // The third vector replaces two presses of the first two
+        assert_eq!(Some(vec![0, 0, 2]), combination(&[[1, 0], [0, 1], [1, 1]], &[3, 3, 1], [2, 2]));
+        assert_eq!(Some(vec![2, 1, 0]), combination(&[[1, 0], [0, 1], [1, 1]], &[1, 1, 5], [2, 1]));
+        assert_eq!(None, combination(&[[2, 0], [0, 2], [2, 2]], &[1, 1, 1], [3, 2]));
+    }
+
+    #[test]
+    fn test_min_cost_combination_2d_errors() {
+        let vectors = [[Integer::from(1), Integer::from(-1)]];
+        assert!(min_cost_combination_2d(&vectors, &integers(&[1]), &[Integer::zero(), Integer::zero()]).is_err());
+
+        let vectors = [[1, 1], [1, 2], [1, 3]].map(|v| v.map(Integer::from));
+        let far = [Integer::from(10_000_000), Integer::from(10_000_000)];
+        assert!(min_cost_combination_2d(&vectors, &integers(&[1, 1, 1]), &far).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use anyhow::*;
+
+// Checked i64 arithmetic by default, arbitrary precision with the `bigint` feature
+#[cfg(not(feature = "bigint"))]
+mod backend {
+    pub type Repr = i64;
+
+    pub fn from_i64(value: i64) -> Repr {
+        value
+    }
+
+    pub fn to_i64(value: &Repr) -> Option<i64> {
+        Some(*value)
+    }
+
+    pub fn add(a: &Repr, b: &Repr) -> Option<Repr> {
+        a.checked_add(*b)
+    }
+
+    pub fn sub(a: &Repr, b: &Repr) -> Option<Repr> {
+        a.checked_sub(*b)
+    }
+
+    pub fn mul(a: &Repr, b: &Repr) -> Option<Repr> {
+        a.checked_mul(*b)
+    }
+
+    pub fn div_rem(a: &Repr, b: &Repr) -> Option<(Repr, Repr)> {
+        Some((a.checked_div(*b)?, a.checked_rem(*b)?))
+    }
+
+    pub fn xor(a: &Repr, b: &Repr) -> Repr {
+        a ^ b
+    }
+
+    pub fn parse(s: &str) -> Option<Repr> {
+        s.parse().ok()
+    }
+}
+
+#[cfg(feature = "bigint")]
+mod backend {
+    use num_bigint::BigInt;
+
+    pub type Repr = BigInt;
+
+    pub fn from_i64(value: i64) -> Repr {
+        BigInt::from(value)
+    }
+
+    pub fn to_i64(value: &Repr) -> Option<i64> {
+        i64::try_from(value).ok()
+    }
+
+    pub fn add(a: &Repr, b: &Repr) -> Option<Repr> {
+        Some(a + b)
+    }
+
+    pub fn sub(a: &Repr, b: &Repr) -> Option<Repr> {
+        Some(a - b)
+    }
+
+    pub fn mul(a: &Repr, b: &Repr) -> Option<Repr> {
+        Some(a * b)
+    }
+
+    pub fn div_rem(a: &Repr, b: &Repr) -> Option<(Repr, Repr)> {
+        if *b == BigInt::ZERO {
+            None
+        } else {
+            Some((a / b, a % b))
+        }
+    }
+
+    pub fn xor(a: &Repr, b: &Repr) -> Repr {
+        a ^ b
+    }
+
+    pub fn parse(s: &str) -> Option<Repr> {
+        s.parse().ok()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(backend::Repr);
+
+impl Integer {
+    pub fn zero() -> Integer {
+        Integer::from(0)
+    }
+
+    pub fn add(&self, other: &Integer) -> Result<Integer> {
+        backend::add(&self.0, &other.0).map(Integer)
+            .ok_or_else(|| anyhow!("Integer overflow in {} + {}", self, other))
+    }
+
+    pub fn sub(&self, other: &Integer) -> Result<Integer> {
+        backend::sub(&self.0, &other.0).map(Integer)
+            .ok_or_else(|| anyhow!("Integer overflow in {} - {}", self, other))
+    }
+
+    pub fn mul(&self, other: &Integer) -> Result<Integer> {
+        backend::mul(&self.0, &other.0).map(Integer)
+            .ok_or_else(|| anyhow!("Integer overflow in {} * {}", self, other))
+    }
+
+    // Quotient and remainder, both truncated towards zero like i64 `/` and `%`
+    pub fn div_rem(&self, other: &Integer) -> Result<(Integer, Integer)> {
+        backend::div_rem(&self.0, &other.0).map(|(q, r)| (Integer(q), Integer(r)))
+            .ok_or_else(|| anyhow!("Invalid division {} / {}", self, other))
+    }
+
+    // Quotient when other divides self without remainder
+    pub fn div_exact(&self, other: &Integer) -> Result<Option<Integer>> {
+        if other.is_zero() {
+            return Ok(None);
+        }
+        let (quotient, remainder) = self.div_rem(other)?;
+        Ok(if remainder.is_zero() { Some(quotient) } else { None })
+    }
+
+    // Quotient rounded towards negative infinity
+    pub fn div_floor(&self, other: &Integer) -> Result<Integer> {
+        let (quotient, remainder) = self.div_rem(other)?;
+        if !remainder.is_zero() && remainder.is_negative() != other.is_negative() {
+            quotient.sub(&Integer::from(1))
+        } else {
+            Ok(quotient)
+        }
+    }
+
+    // Quotient rounded towards positive infinity
+    pub fn div_ceil(&self, other: &Integer) -> Result<Integer> {
+        self.neg()?.div_floor(other)?.neg()
+    }
+
+    pub fn neg(&self) -> Result<Integer> {
+        Integer::zero().sub(self)
+    }
+
+    pub fn xor(&self, other: &Integer) -> Integer {
+        Integer(backend::xor(&self.0, &other.0))
+    }
+
+    pub fn pow10(exponent: usize) -> Result<Integer> {
+        let ten = Integer::from(10);
+        (0..exponent).try_fold(Integer::from(1), |acc, _| acc.mul(&ten))
+    }
+
+    // Decimal concatenation, 12 || 345 = 12345
+    pub fn concat(&self, other: &Integer) -> Result<Integer> {
+        if other.is_negative() {
+            bail!("Can't concatenate negative {} to {}", other, self);
+        }
+        let shifted = self.mul(&Integer::pow10(other.to_string().len())?)?;
+        if self.is_negative() {
+            shifted.sub(other)
+        } else {
+            shifted.add(other)
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Integer::zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        *self < Integer::zero()
+    }
+
+    pub fn to_i64(&self) -> Result<i64> {
+        backend::to_i64(&self.0).ok_or_else(|| anyhow!("{} doesn't fit into i64", self))
+    }
+
+    pub fn sum<'a, I: IntoIterator<Item = &'a Integer>>(values: I) -> Result<Integer> {
+        values.into_iter().try_fold(Integer::zero(), |acc, value| acc.add(value))
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(value: i64) -> Integer {
+        Integer(backend::from_i64(value))
+    }
+}
+
+impl FromStr for Integer {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Integer> {
+        backend::parse(s).map(Integer).ok_or_else(|| anyhow!("Invalid integer \"{}\"", s))
+    }
+}
+
+impl Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(Integer::from(12345), Integer::from(12).concat(&Integer::from(345)).unwrap());
+        assert_eq!(Integer::from(100), Integer::from(10).concat(&Integer::from(0)).unwrap());
+        assert_eq!(Integer::from(-123), Integer::from(-1).concat(&Integer::from(23)).unwrap());
+    }
+
+    #[test]
+    fn test_div_exact() {
+        assert_eq!(Some(Integer::from(4)), Integer::from(12).div_exact(&Integer::from(3)).unwrap());
+        assert_eq!(None, Integer::from(13).div_exact(&Integer::from(3)).unwrap());
+        assert_eq!(None, Integer::from(13).div_exact(&Integer::zero()).unwrap());
+    }
+
+    #[test]
+    fn test_div_floor_ceil() {
+        let div = |a: i64, b: i64| {
+            let (a, b) = (Integer::from(a), Integer::from(b));
+            (a.div_floor(&b).unwrap().to_i64().unwrap(), a.div_ceil(&b).unwrap().to_i64().unwrap())
+        };
+        assert_eq!((2, 3), div(7, 3));
+        assert_eq!((-3, -2), div(-7, 3));
+        assert_eq!((-3, -2), div(7, -3));
+        assert_eq!((2, 3), div(-7, -3));
+        assert_eq!((-2, -2), div(-6, 3));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Integer::from(-42), "-42".parse::<Integer>().unwrap());
+        assert_eq!("Invalid integer \"4x\"", "4x".parse::<Integer>().unwrap_err().to_string());
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_overflow() {
+        let max = Integer::from(i64::MAX);
+        assert_eq!(
+            "Integer overflow in 9223372036854775807 * 2",
+            max.mul(&Integer::from(2)).unwrap_err().to_string()
+        );
+        assert!(Integer::from(922337203685477580).concat(&Integer::from(80)).is_err());
+        assert!("92233720368547758070".parse::<Integer>().is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_overflow() {
+        let max = Integer::from(i64::MAX);
+        let doubled = max.mul(&Integer::from(2)).unwrap();
+        assert_eq!("18446744073709551614", doubled.to_string());
+        assert!(doubled.to_i64().is_err());
+        assert_eq!(max, doubled.div_rem(&Integer::from(2)).unwrap().0);
+    }
+}
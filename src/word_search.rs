@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::io::BufRead;
+use anyhow::*;
+
+// Linear map from pattern offsets (row, column) to grid offsets
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+pub struct Orientation {
+    pub row: (isize, isize),
+    pub column: (isize, isize),
+}
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation { row: (1, 0), column: (0, 1) };
+
+    // Words read in the eight directions, the first one is left to right
+    pub fn compass() -> Vec<Orientation> {
+        [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)].iter()
+            .map(|&(dr, dc)| Orientation { row: (dc, dr), column: (-dr, dc) })
+            .collect()
+    }
+
+    // Rotations by quarter turns and their mirror images
+    pub fn symmetries() -> Vec<Orientation> {
+        let rotations = [((1, 0), (0, 1)), ((0, -1), (1, 0)), ((-1, 0), (0, -1)), ((0, 1), (-1, 0))];
+        rotations.iter()
+            .flat_map(|&(row, column)| [
+                Orientation { row, column },
+                Orientation { row: (row.0, -row.1), column: (column.0, -column.1) },
+            ])
+            .collect()
+    }
+
+    pub fn apply(&self, (row, column): (isize, isize)) -> (isize, isize) {
+        (self.row.0 * row + self.row.1 * column, self.column.0 * row + self.column.1 * column)
+    }
+
+    // Where the pattern's first row goes
+    pub fn direction(&self) -> (isize, isize) {
+        self.apply((0, 1))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub name: String,
+    // Letters by offset from the pattern's top-left corner, wildcards are left out
+    cells: Vec<((isize, isize), char)>,
+    orientations: Vec<Orientation>,
+}
+
+impl Pattern {
+    pub fn word(word: &str) -> Pattern {
+        Pattern {
+            name: word.to_string(),
+            cells: word.chars().enumerate().map(|(i, c)| ((0, i as isize), c)).collect(),
+            orientations: Orientation::compass(),
+        }
+    }
+
+    // Rows of the shape, `wildcard` matches any letter
+    pub fn shape(name: &str, rows: &[&str], wildcard: char) -> Result<Pattern> {
+        let cells: Vec<((isize, isize), char)> = rows.iter().enumerate()
+            .flat_map(|(r, row)| row.chars().enumerate().map(move |(c, letter)| ((r as isize, c as isize), letter)))
+            .filter(|&(_, letter)| letter != wildcard)
+            .collect();
+        if cells.is_empty() {
+            bail!("Pattern {} has no letters", name);
+        }
+        Ok(Pattern { name: name.to_string(), cells, orientations: Orientation::symmetries() })
+    }
+
+    pub fn with_orientations(self, orientations: Vec<Orientation>) -> Pattern {
+        Pattern { orientations, ..self }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    pub pattern: String,
+    // Grid position of the pattern's top-left corner
+    pub anchor: (usize, usize),
+    pub orientation: Orientation,
+    // Matched grid cells in the pattern's letter order
+    pub cells: Vec<(usize, usize)>,
+}
+
+pub struct Grid {
+    rows: Vec<Vec<char>>,
+}
+
+impl Grid {
+    pub fn new(rows: &[&str]) -> Grid {
+        Grid { rows: rows.iter().map(|row| row.chars().collect()).collect() }
+    }
+
+    pub fn parse<R: BufRead>(reader: R) -> Result<Grid> {
+        let mut rows = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.is_empty() {
+                rows.push(line.chars().collect());
+            }
+        }
+        Ok(Grid { rows })
+    }
+
+    fn get(&self, (row, column): (isize, isize)) -> Option<char> {
+        if row < 0 || column < 0 {
+            return None;
+        }
+        self.rows.get(row as usize)?.get(column as usize).copied()
+    }
+
+    // Occurrences in every orientation of the pattern, an occurrence covering the same cells as an
+    // earlier one (e.g. a palindrome read backwards) is reported once
+    pub fn find(&self, pattern: &Pattern) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut seen: HashSet<Vec<(usize, usize)>> = HashSet::new();
+        for &orientation in pattern.orientations.iter() {
+            let offsets: Vec<((isize, isize), char)> = pattern.cells.iter()
+                .map(|&(offset, letter)| (orientation.apply(offset), letter))
+                .collect();
+            for (r, row) in self.rows.iter().enumerate() {
+                for c in 0..row.len() {
+                    let anchor = (r as isize, c as isize);
+                    let found = offsets.iter()
+                        .all(|&((dr, dc), letter)| self.get((anchor.0 + dr, anchor.1 + dc)) == Some(letter));
+                    if !found {
+                        continue;
+                    }
+
+                    let cells: Vec<(usize, usize)> = offsets.iter()
+                        .map(|&((dr, dc), _)| ((anchor.0 + dr) as usize, (anchor.1 + dc) as usize))
+                        .collect();
+                    let mut key = cells.clone();
+                    key.sort();
+                    if seen.insert(key) {
+                        matches.push(Match { pattern: pattern.name.clone(), anchor: (r, c), orientation, cells });
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    pub fn find_all(&self, patterns: &[Pattern]) -> Vec<Match> {
+        patterns.iter().flat_map(|pattern| self.find(pattern)).collect()
+    }
+
+    // The grid with letters outside of the matches replaced by `background`
+    pub fn highlight(&self, matches: &[Match], background: char) -> String {
+        let matched: HashSet<(usize, usize)> = matches.iter().flat_map(|m| m.cells.iter().copied()).collect();
+        self.rows.iter().enumerate()
+            .map(|(r, row)| {
+                let mut line: String = row.iter().enumerate()
+                    .map(|(c, &letter)| if matched.contains(&(r, c)) { letter } else { background })
+                    .collect();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_word_directions() {
+        let grid = Grid::new(&["XMAS", "MM..", "A.A.", "S..S"]);
+        let matches = grid.find(&Pattern::word("XMAS"));
+        assert_eq!(
+            vec![(0, 1), (1, 1), (1, 0)],
+            matches.iter().map(|m| m.orientation.direction()).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(0, 0), (1, 1), (2, 2), (3, 3)], matches[1].cells);
+        assert!(matches.iter().all(|m| m.anchor == (0, 0)));
+    }
+
+    #[test]
+    fn test_palindrome_reported_once() {
+        let grid = Grid::new(&["ABA"]);
+        assert_eq!(1, grid.find(&Pattern::word("ABA")).len());
+        assert_eq!(2, grid.find(&Pattern::word("A")).len());
+    }
+
+    #[test]
+    fn test_shape_with_wildcards() {
+        let pattern = Pattern::shape("L", &["A.", "BC"], '.').unwrap();
+        let grid = Grid::new(&["AXXA", "BCCB"]);
+        let matches = grid.find(&pattern);
+        assert_eq!(2, matches.len());
+        assert_eq!(vec![(1, 1), (1, 2)], matches.iter().map(|m| m.cells[2]).collect::<Vec<_>>());
+
+        let fixed = pattern.with_orientations(vec![Orientation::IDENTITY]);
+        assert_eq!(vec![(0, 0)], grid.find(&fixed).iter().map(|m| m.anchor).collect::<Vec<_>>());
+        assert!(Pattern::shape("empty", &["..", "."], '.').is_err());
+    }
+
+    #[test]
+    fn test_highlight() {
+        let grid = Grid::new(&["XMASZ", "QQQQQ", "ZSAMX"]);
+        let patterns = [Pattern::word("XMAS"), Pattern::word("QQ")];
+        let matches = grid.find_all(&patterns);
+        assert_eq!(6, matches.len());
+        assert_eq!(
+            indoc! {"
+                XMAS.
+                QQQQQ
+                .SAMX
+            "},
+            grid.highlight(&matches, '.')
+        );
+    }
+}